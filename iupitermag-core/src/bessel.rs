@@ -0,0 +1,125 @@
+use std::f64::consts::{FRAC_PI_4, PI};
+
+/// Argument above which the Hankel asymptotic expansion is used instead of the power series.
+const ASYMPTOTIC_THRESHOLD: f64 = 12.;
+
+/// Calculate the Bessel function of the first kind of order zero, J0(x).
+pub fn bessel_j0(x: f64) -> f64 {
+    bessel_j0_j1(x).0
+}
+
+/// Calculate the Bessel function of the first kind of order one, J1(x).
+pub fn bessel_j1(x: f64) -> f64 {
+    bessel_j0_j1(x).1
+}
+
+/// Calculate both J0(x) and J1(x). This is cheaper than calling [`bessel_j0`] and [`bessel_j1`]
+/// separately, since the trigonometric terms of the asymptotic expansion are shared.
+pub fn bessel_j0_j1(x: f64) -> (f64, f64) {
+    let ax = x.abs();
+
+    let (j0, j1) = if ax < ASYMPTOTIC_THRESHOLD {
+        power_series(ax)
+    } else {
+        hankel_asymptotic(ax)
+    };
+
+    // J0 is even and J1 is odd.
+    if x < 0. { (j0, -j1) } else { (j0, j1) }
+}
+
+/// Ascending power series, accurate to ~1e-12 for |x| < 12.
+fn power_series(x: f64) -> (f64, f64) {
+    let y = 0.25 * x * x;
+
+    let mut term0: f64 = 1.;
+    let mut term1: f64 = 1.;
+    let mut j0 = 1.;
+    let mut j1 = 1.;
+
+    let mut k = 1.;
+    while term0.abs() > 1e-17 || term1.abs() > 1e-17 {
+        term0 *= -y / (k * k);
+        term1 *= -y / (k * (k + 1.));
+        j0 += term0;
+        j1 += term1;
+        k += 1.;
+    }
+
+    (j0, 0.5 * x * j1)
+}
+
+/// Hankel's asymptotic expansion for large arguments. The series is truncated at its smallest
+/// term, which gives an error of roughly exp(-2x).
+fn hankel_asymptotic(x: f64) -> (f64, f64) {
+    let (p0, q0) = hankel_pq(0., x);
+    let (p1, q1) = hankel_pq(1., x);
+
+    let amplitude = (2. / (PI * x)).sqrt();
+    let (sin_chi, cos_chi) = (x - FRAC_PI_4).sin_cos();
+
+    // For order one the phase is shifted by a further -pi/2, so cos -> sin and sin -> -cos.
+    let j0 = amplitude * (p0 * cos_chi - q0 * sin_chi);
+    let j1 = amplitude * (p1 * sin_chi + q1 * cos_chi);
+    (j0, j1)
+}
+
+/// The `P` and `Q` auxiliary series of the Hankel expansion for order `nu`.
+fn hankel_pq(nu: f64, x: f64) -> (f64, f64) {
+    let mu = 4. * nu * nu;
+
+    let mut p = 1.;
+    let mut q = 0.;
+    let mut term: f64 = 1.;
+
+    for k in 1..100 {
+        let odd = (2 * k - 1) as f64;
+        let next = term * (mu - odd * odd) / (k as f64 * 8. * x);
+
+        // Stop once the series starts diverging or has converged.
+        if next.abs() >= term.abs() || next.abs() < 1e-17 {
+            break;
+        }
+        term = next;
+
+        match k % 4 {
+            1 => q += term,
+            2 => p -= term,
+            3 => q -= term,
+            _ => p += term,
+        }
+    }
+    (p, q)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_bessel_j0_j1() {
+        use crate::bessel::bessel_j0_j1;
+
+        // Reference values from mpmath.besselj.
+        let val_check = [
+            (0.5, 0.938469807240813, 0.242268457674874),
+            (1., 0.765197686557967, 0.440050585744934),
+            (5., -0.177596771314338, -0.327579137591465),
+            (10., -0.245935764451348, 0.0434727461688614),
+            (11.99, 0.0454515603528586, -0.224099371266249),
+            (12., 0.0476893107968335, -0.223447104490628),
+            (20., 0.167024664340583, 0.06683312417585),
+            (123.4, -0.0715255367192602, -0.00685099988565437),
+            (-5., -0.177596771314338, 0.327579137591465),
+        ];
+
+        for (x, j0_test, j1_test) in val_check {
+            let (j0, j1) = bessel_j0_j1(x);
+            assert!(
+                (j0 - j0_test).abs() < 1e-10 && (j1 - j1_test).abs() < 1e-10,
+                "Bessel Test Failed at x = {:?}: \n Calculated {:?}, Expected {:?}",
+                x,
+                (j0, j1),
+                (j0_test, j1_test)
+            );
+        }
+    }
+}
@@ -0,0 +1,500 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::external::ExternalHarmonicField;
+use crate::internal::InternalField;
+use crate::models::{JUPITER_RADIUS_KM, ModelInfo};
+use crate::time::Time;
+
+/// Layout of a file containing internal field coefficients.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CoefficientFormat {
+    /// Whitespace separated rows of `g n m value` or `h n m value`, in nT, as used by
+    /// JupiterMag and the con2020 codes. Blank lines and lines starting with `#` are ignored.
+    /// External coefficients are given as `q n m value` and `s n m value` rows, so one file can
+    /// hold both parts of a fitted model. Each coefficient may only be given once, and `h` and
+    /// `s` rows must have m > 0.
+    ///
+    /// The model name is written as a `#` comment, which is not read back, so only the JSON
+    /// format round-trips the model metadata.
+    Text,
+    /// A JSON object with optional model metadata and the coefficients as triangular arrays,
+    /// where row `n` of `g` and `h` contains the coefficients for m = 0..=n in nT. Secular
+    /// variation can be given as `dg` and `dh` (nT / year) together with an `epoch` (decimal
+    /// year):
+    ///
+    /// ```json
+    /// {
+    ///   "name": "MyModel",
+    ///   "reference": "Where the model comes from",
+    ///   "planet_radius_km": 71492.0,
+    ///   "recommended_degree": 1,
+    ///   "g": [[0.0], [410244.7, -71498.3]],
+    ///   "h": [[0.0], [0.0, 21330.5]]
+    /// }
+    /// ```
+    ///
+    /// External coefficients are given as triangular `q` and `s` arrays in the same way.
+    Json,
+}
+
+impl CoefficientFormat {
+    /// Guess the format from the extension of `path`. Files ending in `.json` are JSON, and
+    /// everything else is treated as text.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|x| x.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => CoefficientFormat::Json,
+            _ => CoefficientFormat::Text,
+        }
+    }
+}
+
+/// JSON representation of an internal field model.
+#[derive(Serialize, Deserialize)]
+struct JsonModel {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reference: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    planet_radius_km: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    recommended_degree: Option<usize>,
+    g: Vec<Vec<f64>>,
+    h: Vec<Vec<f64>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    epoch: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dg: Option<Vec<Vec<f64>>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dh: Option<Vec<Vec<f64>>>,
+}
+
+/// JSON representation of an external field model. Other keys, such as the internal
+/// coefficients, are ignored.
+#[derive(Serialize, Deserialize)]
+struct JsonExternalModel {
+    q: Vec<Vec<f64>>,
+    s: Vec<Vec<f64>>,
+}
+
+impl InternalField {
+    /// Load an internal field model from a file. The format is chosen from the file extension,
+    /// see [`CoefficientFormat::from_path`].
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        Self::from_reader(BufReader::new(file), CoefficientFormat::from_path(path))
+    }
+
+    /// Load an internal field model from a reader containing coefficients in `format`.
+    pub fn from_reader<R: Read>(reader: R, format: CoefficientFormat) -> Result<Self, Error> {
+        match format {
+            CoefficientFormat::Text => read_text(BufReader::new(reader)),
+            CoefficientFormat::Json => read_json(reader),
+        }
+    }
+
+    /// Write the coefficients (and for JSON, the model metadata) of this model to a file. The
+    /// format is chosen from the file extension, see [`CoefficientFormat::from_path`].
+    pub fn to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.to_writer(&mut writer, CoefficientFormat::from_path(path))?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Write the coefficients (and for JSON, the model metadata) of this model to `writer`.
+    pub fn to_writer<W: Write>(
+        &self,
+        mut writer: W,
+        format: CoefficientFormat,
+    ) -> Result<(), Error> {
+        let (g, h) = self.get_coefficients();
+        let degree = g.nrows() - 1;
+
+        match format {
+            CoefficientFormat::Text => {
+                if let Some(info) = self.model_info() {
+                    writeln!(writer, "# {}", info.name)?;
+                }
+                for n in 1..degree + 1 {
+                    for m in 0..n + 1 {
+                        writeln!(writer, "g {} {} {}", n, m, g[[n, m]])?;
+                        if m > 0 {
+                            writeln!(writer, "h {} {} {}", n, m, h[[n, m]])?;
+                        }
+                    }
+                }
+            }
+            CoefficientFormat::Json => {
+                let info = self.model_info();
+                let sv = self.get_secular_variation();
+
+                let model = JsonModel {
+                    name: info.map(|x| x.name.to_string()),
+                    reference: info.map(|x| x.reference.to_string()),
+                    planet_radius_km: info.map(|x| x.planet_radius_km),
+                    recommended_degree: info.map(|x| x.recommended_degree.min(degree)),
+                    g: to_triangle(&g),
+                    h: to_triangle(&h),
+                    epoch: self.epoch(),
+                    dg: sv.as_ref().map(|(dg, _)| to_triangle(dg)),
+                    dh: sv.as_ref().map(|(_, dh)| to_triangle(dh)),
+                };
+                serde_json::to_writer_pretty(&mut writer, &model)
+                    .map_err(|e| Error::Parse(e.to_string()))?;
+                writeln!(writer)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ExternalHarmonicField {
+    /// Load an external field model from a file. The format is chosen from the file extension,
+    /// see [`CoefficientFormat::from_path`].
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        Self::from_reader(BufReader::new(file), CoefficientFormat::from_path(path))
+    }
+
+    /// Load an external field model from a reader containing coefficients in `format`.
+    pub fn from_reader<R: Read>(reader: R, format: CoefficientFormat) -> Result<Self, Error> {
+        let (q, s) = match format {
+            CoefficientFormat::Text => read_text_rows(BufReader::new(reader), ["q", "s"])?,
+            CoefficientFormat::Json => {
+                let model: JsonExternalModel =
+                    serde_json::from_reader(reader).map_err(|e| Error::Parse(e.to_string()))?;
+                from_triangles(&model.q, &model.s, "q and s")?
+            }
+        };
+        ExternalHarmonicField::try_new(q, s, None)
+    }
+
+    /// Write the coefficients of this model to a file. The format is chosen from the file
+    /// extension, see [`CoefficientFormat::from_path`].
+    pub fn to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.to_writer(&mut writer, CoefficientFormat::from_path(path))?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Write the coefficients of this model to `writer`.
+    pub fn to_writer<W: Write>(
+        &self,
+        mut writer: W,
+        format: CoefficientFormat,
+    ) -> Result<(), Error> {
+        let (q, s) = self.get_coefficients();
+
+        match format {
+            CoefficientFormat::Text => {
+                for n in 1..q.nrows() {
+                    for m in 0..n + 1 {
+                        writeln!(writer, "q {} {} {}", n, m, q[[n, m]])?;
+                        if m > 0 {
+                            writeln!(writer, "s {} {} {}", n, m, s[[n, m]])?;
+                        }
+                    }
+                }
+            }
+            CoefficientFormat::Json => {
+                let model = JsonExternalModel {
+                    q: to_triangle(&q),
+                    s: to_triangle(&s),
+                };
+                serde_json::to_writer_pretty(&mut writer, &model)
+                    .map_err(|e| Error::Parse(e.to_string()))?;
+                writeln!(writer)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parse `g n m value` / `h n m value` rows.
+fn read_text<R: BufRead>(reader: R) -> Result<InternalField, Error> {
+    let (g, h) = read_text_rows(reader, ["g", "h"])?;
+    InternalField::try_new("Custom", Some(g), Some(h), None)
+}
+
+/// Parse `kind n m value` rows into square arrays for the two coefficient kinds in `kinds`.
+/// Rows of the other known kinds (g, h, q and s) are skipped.
+fn read_text_rows<R: BufRead>(
+    reader: R,
+    kinds: [&str; 2],
+) -> Result<(Array2<f64>, Array2<f64>), Error> {
+    let mut entries: Vec<(bool, usize, usize, f64)> = vec![];
+    let mut seen = HashSet::new();
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let parse_error = |message: &str| Error::Parse(format!("line {}: {message}", i + 1));
+
+        let columns: Vec<&str> = line.split_whitespace().collect();
+        let [kind, n, m, value] = columns[..] else {
+            return Err(parse_error("expected 4 columns (g/h, n, m, value)"));
+        };
+
+        let kind = kind.to_ascii_lowercase();
+        if !["g", "h", "q", "s"].contains(&kind.as_str()) {
+            return Err(parse_error("first column must be g, h, q or s"));
+        }
+        if !kinds.contains(&kind.as_str()) {
+            continue;
+        }
+        let is_first = kind == kinds[0];
+        let n: usize = n.parse().map_err(|_| parse_error("invalid degree n"))?;
+        let m: usize = m.parse().map_err(|_| parse_error("invalid order m"))?;
+        let value: f64 = value.parse().map_err(|_| parse_error("invalid value"))?;
+
+        if m > n {
+            return Err(parse_error("order m must not exceed degree n"));
+        }
+        if !is_first && m == 0 {
+            return Err(parse_error(&format!(
+                "{kind} coefficients must have order m > 0"
+            )));
+        }
+        if !seen.insert((is_first, n, m)) {
+            return Err(parse_error(&format!(
+                "duplicate coefficient {kind} {n} {m}"
+            )));
+        }
+        entries.push((is_first, n, m, value));
+    }
+
+    let Some(degree) = entries.iter().map(|x| x.1).max() else {
+        return Err(Error::Parse(format!(
+            "no {} or {} coefficients found",
+            kinds[0], kinds[1]
+        )));
+    };
+
+    let mut first = Array2::<f64>::zeros((degree + 1, degree + 1));
+    let mut second = Array2::<f64>::zeros((degree + 1, degree + 1));
+    for (is_first, n, m, value) in entries {
+        let target = if is_first { &mut first } else { &mut second };
+        target[[n, m]] = value;
+    }
+
+    Ok((first, second))
+}
+
+/// Parse the JSON representation, see [`CoefficientFormat::Json`].
+fn read_json<R: Read>(reader: R) -> Result<InternalField, Error> {
+    let model: JsonModel =
+        serde_json::from_reader(reader).map_err(|e| Error::Parse(e.to_string()))?;
+
+    let (g, h) = from_triangles(&model.g, &model.h, "g and h")?;
+    let degree = g.nrows() - 1;
+
+    let mut field = InternalField::try_new("Custom", Some(g), Some(h), None)?;
+
+    match (model.dg, model.dh, model.epoch) {
+        (Some(dg), Some(dh), Some(epoch)) => {
+            let (dg, dh) = from_triangles(&dg, &dh, "dg and dh")?;
+            field = field.with_secular_variation(dg, dh, Time::DecimalYear(epoch))?;
+        }
+        (None, None, _) => {}
+        _ => {
+            return Err(Error::Parse(
+                "secular variation needs all of dg, dh and epoch".to_string(),
+            ));
+        }
+    }
+
+    let Some(name) = model.name else {
+        return Ok(field);
+    };
+
+    Ok(field.with_model_info(ModelInfo {
+        name: Cow::Owned(name),
+        reference: Cow::Owned(model.reference.unwrap_or_default()),
+        max_degree: degree,
+        recommended_degree: model.recommended_degree.unwrap_or(degree).min(degree),
+        planet_radius_km: model.planet_radius_km.unwrap_or(JUPITER_RADIUS_KM),
+        bundled: false,
+    }))
+}
+
+/// Convert the lower triangle of a coefficient array into rows of length n + 1.
+fn to_triangle(x: &Array2<f64>) -> Vec<Vec<f64>> {
+    x.rows()
+        .into_iter()
+        .enumerate()
+        .map(|(n, row)| row.iter().take(n + 1).copied().collect())
+        .collect()
+}
+
+/// Convert triangular rows of coefficients into square arrays, checking their shapes.
+fn from_triangles(
+    g_rows: &[Vec<f64>],
+    h_rows: &[Vec<f64>],
+    names: &str,
+) -> Result<(Array2<f64>, Array2<f64>), Error> {
+    if g_rows.is_empty() || g_rows.len() != h_rows.len() {
+        return Err(Error::Parse(format!(
+            "{names} must be non-empty and have the same number of rows"
+        )));
+    }
+    let size = g_rows.len();
+
+    let mut g = Array2::<f64>::zeros((size, size));
+    let mut h = Array2::<f64>::zeros((size, size));
+    for n in 0..size {
+        if g_rows[n].len() != n + 1 || h_rows[n].len() != n + 1 {
+            return Err(Error::Parse(format!(
+                "row {n} of {names} must contain {} values",
+                n + 1
+            )));
+        }
+        for m in 0..n + 1 {
+            g[[n, m]] = g_rows[n][m];
+            h[[n, m]] = h_rows[n][m];
+        }
+    }
+    Ok((g, h))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_coefficients_round_trip() {
+        use crate::coefficients::CoefficientFormat;
+        use crate::internal::InternalField;
+
+        let field = InternalField::new("VIP4", None, None, None);
+        let (g_test, h_test) = field.get_coefficients();
+
+        for format in [CoefficientFormat::Text, CoefficientFormat::Json] {
+            let mut buffer = vec![];
+            field.to_writer(&mut buffer, format).unwrap();
+            let val = InternalField::from_reader(buffer.as_slice(), format).unwrap();
+            let (g, h) = val.get_coefficients();
+
+            for (x, x_test) in [(&g, &g_test), (&h, &h_test)] {
+                assert!(
+                    (x - x_test).iter().all(|d| d.abs() < 1e-6),
+                    "Coefficient Round Trip Test Failed: \n Calculated {:?}, Expected {:?}",
+                    x,
+                    x_test
+                );
+            }
+
+            if format == CoefficientFormat::Json {
+                let info = val.model_info().unwrap();
+                assert!(
+                    info.name == "VIP4" && info.planet_radius_km == 71323.,
+                    "Coefficient Round Trip Test Failed: \n Calculated {:?}",
+                    info
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_coefficients_read_text() {
+        use crate::coefficients::CoefficientFormat;
+        use crate::error::Error;
+        use crate::internal::InternalField;
+
+        let text = "# A dipole\n\ng 1 0 410244.7\ng 1 1 -71498.3\nh 1 1 21330.5\n";
+        let val = InternalField::from_reader(text.as_bytes(), CoefficientFormat::Text).unwrap();
+        let (g, h) = val.get_coefficients();
+
+        assert!(
+            g.dim() == (2, 2)
+                && (g[[1, 1]] + 71498.3).abs() < 1e-6
+                && (h[[1, 1]] - 21330.5).abs() < 1e-6,
+            "Coefficient Text Test Failed: \n Calculated {:?}, {:?}",
+            g,
+            h
+        );
+
+        for text in [
+            "g 1 2 10.0",
+            "x 1 0 1.0",
+            "g 1 0",
+            "",
+            "g 1 0 1.0\ng 1 0 2.0",
+            "g 1 0 1.0\nh 1 0 5.0",
+        ] {
+            let val = InternalField::from_reader(text.as_bytes(), CoefficientFormat::Text);
+            assert!(
+                matches!(val, Err(Error::Parse(_))),
+                "Coefficient Text Test Failed for {:?}",
+                text
+            );
+        }
+
+        let text = "g 1 0 1.0\n# comment\ng 1 0 2.0\n";
+        let val = InternalField::from_reader(text.as_bytes(), CoefficientFormat::Text);
+        assert!(
+            matches!(&val, Err(Error::Parse(message)) if message.starts_with("line 3")),
+            "Coefficient Text Test Failed: \n Calculated {:?}",
+            val.err()
+        );
+    }
+
+    #[test]
+    fn test_coefficients_external() {
+        use crate::coefficients::CoefficientFormat;
+        use crate::external::ExternalHarmonicField;
+        use crate::internal::InternalField;
+
+        // A combined file holds the internal and external parts of a fitted model.
+        let text = "g 1 0 410244.7\nq 1 0 -15.2\nq 1 1 3.1\ns 1 1 -1.4\nh 1 1 21330.5\n";
+
+        let internal =
+            InternalField::from_reader(text.as_bytes(), CoefficientFormat::Text).unwrap();
+        let external =
+            ExternalHarmonicField::from_reader(text.as_bytes(), CoefficientFormat::Text).unwrap();
+        let (g, _) = internal.get_coefficients();
+        let (q_test, s_test) = external.get_coefficients();
+
+        assert!(
+            (g[[1, 0]] - 410244.7).abs() < 1e-6
+                && (q_test[[1, 0]] + 15.2).abs() < 1e-9
+                && (s_test[[1, 1]] + 1.4).abs() < 1e-9,
+            "Coefficient External Test Failed: \n Calculated {:?}, {:?}",
+            g,
+            q_test
+        );
+
+        for format in [CoefficientFormat::Text, CoefficientFormat::Json] {
+            let mut buffer = vec![];
+            external.to_writer(&mut buffer, format).unwrap();
+            let (q, s) = ExternalHarmonicField::from_reader(buffer.as_slice(), format)
+                .unwrap()
+                .get_coefficients();
+
+            assert!(
+                (&q - &q_test)
+                    .iter()
+                    .chain((&s - &s_test).iter())
+                    .all(|d| d.abs() < 1e-9),
+                "Coefficient External Round Trip Test Failed: \n Calculated {:?}, Expected {:?}",
+                q,
+                q_test
+            );
+        }
+    }
+}
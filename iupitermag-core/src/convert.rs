@@ -4,91 +4,131 @@ use ndarray::{Array1, Array2, ArrayView1, AsArray};
 
 /// Converts a vector of cartesian coordinates to spherical coordinates.
 pub fn pos_xyz_to_rtp<'a, A: AsArray<'a, f64>>(input: A) -> Array1<f64> {
-    let arr: ArrayView1<f64> = input.into();
-
-    let r = (arr[0].powi(2) + arr[1].powi(2) + arr[2].powi(2)).sqrt();
-    Array1::from_vec(vec![r, (arr[2] / r).acos(), (arr[1]).atan2(arr[0])])
+    Array1::from_vec(pos_xyz_to_rtp_array(to_array(input)).to_vec())
 }
 
 /// Converts a vector of spherical coordinates to cartesian coordinates.
 pub fn pos_rtp_to_xyz<'a, A: AsArray<'a, f64>>(input: A) -> Array1<f64> {
-    let arr: ArrayView1<f64> = input.into();
-    Array1::from_vec(vec![
+    Array1::from_vec(pos_rtp_to_xyz_array(to_array(input)).to_vec())
+}
+
+/// Converts a vector from cartesian basis to spherical basis.
+pub fn vec_xyz_to_rtp<'a, A: AsArray<'a, f64>>(input: A, theta: &f64, phi: &f64) -> Array1<f64> {
+    Array1::from_vec(vec_xyz_to_rtp_array(to_array(input), *theta, *phi).to_vec())
+}
+
+/// Converts a vector from spherical basis to cartesian basis.
+pub fn vec_rtp_to_xyz<'a, A: AsArray<'a, f64>>(input: A, theta: &f64, phi: &f64) -> Array1<f64> {
+    Array1::from_vec(vec_rtp_to_xyz_array(to_array(input), *theta, *phi).to_vec())
+}
+
+/// Converts a vector from cylindrical basis to cartesian basis.
+pub fn vec_rpz_to_xyz<'a, A: AsArray<'a, f64>>(input: A, phi: &f64) -> Array1<f64> {
+    Array1::from_vec(vec_rpz_to_xyz_array(to_array(input), *phi).to_vec())
+}
+
+/// Converts a cartesian vector in IAU frame to a cartesian vector in MAG frame.
+pub fn vec_iau_to_mag<'a, A: AsArray<'a, f64>>(input: A, theta_d: f64, phi_d: f64) -> Array1<f64> {
+    Array1::from_vec(vec_iau_to_mag_array(to_array(input), theta_d, phi_d).to_vec())
+}
+
+/// Converts a cartesian vector in MAG frame to a cartesian vector in IAU frame.
+pub fn vec_mag_to_iau<'a, A: AsArray<'a, f64>>(input: A, theta_d: f64, phi_d: f64) -> Array1<f64> {
+    Array1::from_vec(vec_mag_to_iau_array(to_array(input), theta_d, phi_d).to_vec())
+}
+
+/// Same as [`pos_xyz_to_rtp`], but using fixed-size arrays that do not allocate.
+pub fn pos_xyz_to_rtp_array(arr: [f64; 3]) -> [f64; 3] {
+    let r = (arr[0].powi(2) + arr[1].powi(2) + arr[2].powi(2)).sqrt();
+    [r, (arr[2] / r).acos(), (arr[1]).atan2(arr[0])]
+}
+
+/// Same as [`pos_rtp_to_xyz`], but using fixed-size arrays that do not allocate.
+pub fn pos_rtp_to_xyz_array(arr: [f64; 3]) -> [f64; 3] {
+    [
         arr[0] * arr[1].sin() * arr[2].cos(),
         arr[0] * arr[1].sin() * arr[2].sin(),
         arr[0] * arr[1].cos(),
-    ])
+    ]
 }
 
-/// Converts a vector from cartesian basis to spherical basis.
-pub fn vec_xyz_to_rtp<'a, A: AsArray<'a, f64>>(input: A, theta: &f64, phi: &f64) -> Array1<f64> {
-    let arr: ArrayView1<f64> = input.into();
-    let sintheta = theta.sin();
-    let costheta = theta.cos();
-    let sinphi = phi.sin();
-    let cosphi = phi.cos();
+/// Same as [`vec_xyz_to_rtp`], but using fixed-size arrays that do not allocate.
+pub fn vec_xyz_to_rtp_array(arr: [f64; 3], theta: f64, phi: f64) -> [f64; 3] {
+    let (sintheta, costheta) = theta.sin_cos();
+    let (sinphi, cosphi) = phi.sin_cos();
 
-    Array1::from_vec(vec![
+    [
         (arr[0] * sintheta * cosphi) + (arr[1] * sintheta * sinphi) + arr[2] * costheta,
         (arr[0] * costheta * cosphi) + (arr[1] * costheta * sinphi) - arr[2] * sintheta,
         -(arr[0] * sinphi) + (arr[1] * cosphi),
-    ])
+    ]
 }
 
-/// Converts a vector from spherical basis to cartesian basis.
-pub fn vec_rtp_to_xyz<'a, A: AsArray<'a, f64>>(input: A, theta: &f64, phi: &f64) -> Array1<f64> {
-    let arr: ArrayView1<f64> = input.into();
-    let sintheta = theta.sin();
-    let costheta = theta.cos();
-    let sinphi = phi.sin();
-    let cosphi = phi.cos();
+/// Same as [`vec_rtp_to_xyz`], but using fixed-size arrays that do not allocate.
+pub fn vec_rtp_to_xyz_array(arr: [f64; 3], theta: f64, phi: f64) -> [f64; 3] {
+    let (sintheta, costheta) = theta.sin_cos();
+    let (sinphi, cosphi) = phi.sin_cos();
 
-    Array1::from_vec(vec![
+    [
         (arr[0] * sintheta * cosphi) + (arr[1] * costheta * cosphi) - arr[2] * sinphi,
         (arr[0] * sintheta * sinphi) + (arr[1] * costheta * sinphi) + arr[2] * cosphi,
         (arr[0] * costheta) - (arr[1] * sintheta),
-    ])
+    ]
 }
 
-/// Converts a vector from cylindrical basis to cartesian basis.
-pub fn vec_rpz_to_xyz<'a, A: AsArray<'a, f64>>(input: A, phi: &f64) -> Array1<f64> {
-    let arr: ArrayView1<f64> = input.into();
-    let sinphi = phi.sin();
-    let cosphi = phi.cos();
+/// Same as [`vec_rpz_to_xyz`], but using fixed-size arrays that do not allocate.
+pub fn vec_rpz_to_xyz_array(arr: [f64; 3], phi: f64) -> [f64; 3] {
+    let (sinphi, cosphi) = phi.sin_cos();
 
-    Array1::from_vec(vec![
+    [
         arr[0] * cosphi - arr[1] * sinphi,
         arr[0] * sinphi + arr[1] * cosphi,
         arr[2],
-    ])
+    ]
 }
 
-/// Converts a cartesian vector in IAU frame to a cartesian vector in MAG frame.
-pub fn vec_iau_to_mag<'a, A: AsArray<'a, f64>>(input: A, theta_d: f64, phi_d: f64) -> Array1<f64> {
-    let arr: ArrayView1<f64> = input.into();
+/// Same as [`vec_iau_to_mag`], but using fixed-size arrays that do not allocate.
+pub fn vec_iau_to_mag_array(arr: [f64; 3], theta_d: f64, phi_d: f64) -> [f64; 3] {
     // To get from IAU to MAG we need to rotate by -Phi_d along z, then
     // rotate by -Theta_d along Y. The PI subtraction I don't quite understand but it is done by
     // every current sheet code. Let's just have faith.
-
-    let rot_matrix_1 = rot_matrix_z(phi_d - PI);
-    let rot_matrix_2 = rot_matrix_y(theta_d);
-
-    rot_matrix_2.dot(&rot_matrix_1).dot(&arr)
+    rotate_y(rotate_z(arr, phi_d - PI), theta_d)
 }
 
-/// Converts a cartesian vector in MAG frame to a cartesian vector in IAU frame.
-pub fn vec_mag_to_iau<'a, A: AsArray<'a, f64>>(input: A, theta_d: f64, phi_d: f64) -> Array1<f64> {
-    let arr: ArrayView1<f64> = input.into();
+/// Same as [`vec_mag_to_iau`], but using fixed-size arrays that do not allocate.
+pub fn vec_mag_to_iau_array(arr: [f64; 3], theta_d: f64, phi_d: f64) -> [f64; 3] {
     // To get from IAU to MAG we need to rotate by
     // Theta_d along Y
     // Phi_d along Z
     // The PI subtraction I don't quite understand but it is done by
     // every current sheet code. Let's just have faith.
+    rotate_z(rotate_y(arr, -theta_d), PI - phi_d)
+}
 
-    let rot_matrix_1 = rot_matrix_y(-theta_d);
-    let rot_matrix_2 = rot_matrix_z(PI - phi_d);
+/// Apply [`rot_matrix_y`] to a vector.
+fn rotate_y(arr: [f64; 3], angle: f64) -> [f64; 3] {
+    let (sin, cos) = angle.sin_cos();
+    [
+        cos * arr[0] + sin * arr[2],
+        arr[1],
+        -sin * arr[0] + cos * arr[2],
+    ]
+}
 
-    rot_matrix_2.dot(&rot_matrix_1).dot(&arr)
+/// Apply [`rot_matrix_z`] to a vector.
+fn rotate_z(arr: [f64; 3], angle: f64) -> [f64; 3] {
+    let (sin, cos) = angle.sin_cos();
+    [
+        cos * arr[0] - sin * arr[1],
+        sin * arr[0] + cos * arr[1],
+        arr[2],
+    ]
+}
+
+/// Copy the first three elements of an array-like input into a fixed-size array.
+fn to_array<'a, A: AsArray<'a, f64>>(input: A) -> [f64; 3] {
+    let arr: ArrayView1<f64> = input.into();
+    [arr[0], arr[1], arr[2]]
 }
 
 /// Creates a matrix of rotation about the X axis.
@@ -147,3 +187,9 @@ pub fn rot_matrix_z(angle: f64) -> Array2<f64> {
     )
     .unwrap()
 }
+
+/// Creates the matrix that rotates cartesian vectors from the IAU frame to the MAG frame, i.e. the
+/// matrix form of [`vec_iau_to_mag`].
+pub fn rot_matrix_iau_to_mag(theta_d: f64, phi_d: f64) -> Array2<f64> {
+    rot_matrix_y(theta_d).dot(&rot_matrix_z(phi_d - PI))
+}
@@ -1,96 +1,122 @@
-use ndarray::Array1;
 use std::collections::HashMap;
 use std::f64::consts::PI;
 
+use crate::bessel;
 use crate::convert;
+use crate::dipole::Dipole;
+use crate::error::Error;
 use crate::field::Field;
 
 const SMOOTHING_DELTA_RHO: f64 = 1.0;
 
+/// Upper limit of the Hankel integrals when the integrand does not decay exponentially.
+const INTEGRAL_LAMBDA_MAX: f64 = 40.;
+
+/// Smallest Bessel function argument at the upper limit for which the asymptotic tail is used.
+const TAIL_ARGUMENT_MIN: f64 = 12.;
+
+/// Positive nodes and weights of the 8-point Gauss-Legendre rule on [-1, 1].
+const GAUSS_LEGENDRE_NODES: [(f64, f64); 4] = [
+    (0.1834346424956498, 0.362683783378362),
+    (0.525532409916329, 0.3137066458778873),
+    (0.7966664774136267, 0.2223810344533745),
+    (0.9602898564975363, 0.1012285362903763),
+];
+
+/// Names of the current sheet parameters, as used in the `HashMap` representation.
+pub(crate) const PARAM_NAMES: [&str; 7] =
+    ["r_0", "r_1", "d", "mu0_i_2", "theta_d", "phi_d", "i_rho"];
+
 /// Struct defining a current sheet field.
 #[derive(Clone)]
 pub struct CurrentSheetField {
-    r_0: f64,
-    r_1: f64,
-    d: f64,
-    mu0_i_2: f64,
-    theta_d: f64,
-    phi_d: f64,
-    i_rho: f64,
+    params: CurrentSheetParams,
     integration_type: IntegrationType,
 }
 
 impl CurrentSheetField {
-    /// Instantiate a new `CurrentSheetField` object.
+    /// Instantiate a new `CurrentSheetField` object. Panics if the inputs are invalid, see
+    /// [`CurrentSheetField::try_new`] for the fallible version.
     pub fn new(
         field_type: String,
         params: Option<HashMap<String, f64>>,
         integration_type: IntegrationType,
     ) -> Self {
+        Self::try_new(field_type, params, integration_type).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Instantiate a new `CurrentSheetField` object, returning an error if `field_type` is unknown
+    /// or the custom parameters are missing or invalid.
+    pub fn try_new(
+        field_type: String,
+        params: Option<HashMap<String, f64>>,
+        integration_type: IntegrationType,
+    ) -> Result<Self, Error> {
         match field_type.as_str() {
-            "CON2020" => CurrentSheetField {
-                r_0: 7.8,
-                r_1: 51.4,
-                d: 3.6,
-                mu0_i_2: 139.6,
-                theta_d: 9.3 * PI / 180.,
-                phi_d: 204.2 * PI / 180.,
-                i_rho: 16.7,
+            "CON2020" => Ok(CurrentSheetField::from_params(
+                CurrentSheetParams::con2020(),
                 integration_type,
-            },
+            )),
             "Custom" => {
-                let _params = params.expect("params required for Custom field type.");
+                let _params = params.ok_or(Error::MissingInput(
+                    "params required for Custom field type.",
+                ))?;
 
-                let param_names = ["r_0", "r_1", "d", "mu0_i_2", "theta_d", "phi_d", "i_rho"];
+                let params = CurrentSheetParams::try_from(&_params)?;
 
-                for key in param_names {
-                    assert!(_params.contains_key(key), "Missing param - {:}", key);
-                }
-
-                CurrentSheetField {
-                    r_0: _params["r_0"],
-                    r_1: _params["r_1"],
-                    d: _params["d"],
-                    mu0_i_2: _params["mu0_i_2"],
-                    theta_d: _params["theta_d"],
-                    phi_d: _params["phi_d"],
-                    i_rho: _params["i_rho"],
-                    integration_type,
-                }
+                Ok(CurrentSheetField::from_params(params, integration_type))
             }
-            _ => panic!("Unknown field_type: Supported (CON2020, Custom)"),
+            _ => Err(Error::UnknownModel {
+                name: field_type,
+                supported: "CON2020, Custom".to_string(),
+            }),
+        }
+    }
+
+    /// Instantiate a new `CurrentSheetField` from a set of validated parameters.
+    pub fn from_params(params: CurrentSheetParams, integration_type: IntegrationType) -> Self {
+        CurrentSheetField {
+            params,
+            integration_type,
         }
     }
 
-    fn _calc_field(&self, rho: f64, z: f64, a: f64) -> Array1<f64> {
+    fn _calc_field(&self, rho: f64, z: f64, a: f64) -> [f64; 3] {
         match self.integration_type {
             IntegrationType::Analytic => self._calc_field_analytic(rho, z, a),
             IntegrationType::Integral => self._calc_field_integral(rho, z, a),
+            IntegrationType::Hybrid(criterion) => {
+                if criterion.use_integral(rho, z, a, self.params.d) {
+                    self._calc_field_integral(rho, z, a)
+                } else {
+                    self._calc_field_analytic(rho, z, a)
+                }
+            }
         }
     }
 
-    fn _calc_field_analytic(&self, rho: f64, z: f64, a: f64) -> Array1<f64> {
-        let m_neg = z - self.d;
-        let m_pos = z + self.d;
+    fn _calc_field_analytic(&self, rho: f64, z: f64, a: f64) -> [f64; 3] {
+        let m_neg = z - self.params.d;
+        let m_pos = z + self.params.d;
 
         let inverse_rho = if rho == 0.0 { 1e-12 } else { 1.0 / rho };
 
-        let z_star = if z.abs() <= self.d.abs() {
+        let z_star = if z.abs() <= self.params.d.abs() {
             z
         } else {
-            z.signum() * self.d
+            z.signum() * self.params.d
         };
 
         let (b_rho_large, b_z_large) = {
             let n_neg = (rho.powi(2) + m_neg.powi(2)).sqrt();
             let n_pos = (rho.powi(2) + m_pos.powi(2)).sqrt();
 
-            let b_rho = self.mu0_i_2
+            let b_rho = self.params.mu0_i_2
                 * (inverse_rho * (n_neg - n_pos)
                     + rho * a.powi(2) / 4. * (1. / n_pos.powi(3) - 1. / n_neg.powi(3))
                     + 2. * inverse_rho * z_star);
 
-            let b_z = self.mu0_i_2
+            let b_z = self.params.mu0_i_2
                 * (((m_pos + n_pos) / (m_neg + n_neg)).ln()
                     + a.powi(2) / 4. * (m_pos / n_pos.powi(3) - m_neg / n_neg.powi(3)));
             (b_rho, b_z)
@@ -103,11 +129,11 @@ impl CurrentSheetField {
             let p_neg = a.powi(2) - 2. * m_neg.powi(2);
             let p_pos = a.powi(2) - 2. * m_pos.powi(2);
 
-            let b_rho = self.mu0_i_2
+            let b_rho = self.params.mu0_i_2
                 * (rho * 0.5 * (1. / n_neg - 1. / n_pos)
                     + rho.powi(3) / 16. * (p_neg / n_neg.powi(5) - p_pos / n_pos.powi(5)));
 
-            let b_z = self.mu0_i_2
+            let b_z = self.params.mu0_i_2
                 * (((m_pos + n_pos) / (m_neg + n_neg)).ln()
                     + rho.powi(2) / 4. * (m_pos / n_pos.powi(3) - m_neg / n_neg.powi(3)));
             (b_rho, b_z)
@@ -123,27 +149,271 @@ impl CurrentSheetField {
         let b_phi = if rho == 0.0 {
             0.0
         } else {
-            -2.7975 * self.i_rho / rho * z_star / self.d
+            -2.7975 * self.params.i_rho / rho * z_star / self.params.d
         };
         // println!("{}, {}, {}", b_rho, b_phi, b_z);
-        Array1::from_vec(vec![b_rho, b_phi, b_z])
+        [b_rho, b_phi, b_z]
     }
 
-    fn _calc_field_integral(&self, _rho: f64, _z: f64, _a: f64) -> Array1<f64> {
-        unimplemented!();
-        // let b_rho: f64;
-        // let b_phi: f64;
-        // let b_z: f64;
+    fn _calc_field_integral(&self, rho: f64, z: f64, a: f64) -> [f64; 3] {
+        let d = self.params.d;
+        let abs_z = z.abs();
+        let inside = abs_z < d;
+
+        // Outside the sheet, both integrands decay as exp(-lambda * (|z| - d)). Inside the sheet
+        // only the Brho integrand decays (as exp(-lambda * (d - |z|))), while the Bz integrand
+        // falls off algebraically.
+        // Integrands that do not decay are integrated at least until the larger of the two Bessel
+        // function arguments is large enough for its asymptotic form, which the tail uses.
+        let lambda_limit = INTEGRAL_LAMBDA_MAX.max(TAIL_ARGUMENT_MIN / rho.max(a));
+        let decay = (abs_z - d).abs();
+        let lambda_max_rho = integral_cutoff(decay, lambda_limit);
+        let lambda_max_z = if inside {
+            lambda_limit
+        } else {
+            integral_cutoff(decay, lambda_limit)
+        };
+        let lambda_max = lambda_max_rho.max(lambda_max_z);
+
+        // Weights multiplying the Bessel functions in the integrands (already divided by lambda
+        // at the call site). Written with exponentials of negative arguments so that nothing
+        // overflows at large lambda.
+        let weights = |lambda: f64| -> (f64, f64) {
+            if inside {
+                let e_neg = (-lambda * (d - z)).exp_m1();
+                let e_pos = (-lambda * (d + z)).exp_m1();
+                // sinh(lambda z) exp(-lambda d) and 1 - cosh(lambda z) exp(-lambda d)
+                (0.5 * (e_neg - e_pos), -0.5 * (e_neg + e_pos))
+            } else {
+                // sinh(lambda d) exp(-lambda |z|)
+                let w = 0.5 * ((-lambda * (abs_z - d)).exp() - (-lambda * (abs_z + d)).exp());
+                (w, w)
+            }
+        };
+
+        // The panel width resolves the oscillations of J(lambda rho) J0(lambda a), and the
+        // exponential terms near lambda = 0.
+        let panel_width = (PI / (rho + a)).min(2. / (abs_z + d));
+        let half_width = 0.5 * panel_width;
+        let num_panels = (lambda_max / panel_width).ceil() as usize;
+
+        let mut int_rho = 0.;
+        let mut int_z = 0.;
+
+        for i in 0..num_panels {
+            let lambda_0 = i as f64 * panel_width;
+            let centre = lambda_0 + half_width;
 
-        // b_rho = f64::NAN;
-        // b_phi = f64::NAN;
-        // b_z = f64::NAN;
+            let do_rho = lambda_0 < lambda_max_rho;
+            let do_z = lambda_0 < lambda_max_z;
 
-        // Array1::from_vec(vec![b_rho, b_phi, b_z])
+            for (node, weight) in GAUSS_LEGENDRE_NODES {
+                for lambda in [centre - half_width * node, centre + half_width * node] {
+                    let (j0_rho, j1_rho) = bessel::bessel_j0_j1(lambda * rho);
+                    let j0_a = bessel::bessel_j0(lambda * a);
+                    let (w_rho, w_z) = weights(lambda);
+
+                    if do_rho {
+                        int_rho += weight * half_width * j1_rho * j0_a * w_rho / lambda;
+                    }
+                    if do_z {
+                        int_z += weight * half_width * j0_rho * j0_a * w_z / lambda;
+                    }
+                }
+            }
+        }
+
+        // Integrands that were cut off before decaying get an asymptotic tail. When both Bessel
+        // function arguments are large, the leading-order forms J0(x) ~ sqrt(2 / pi x)
+        // cos(x - pi / 4) and J1(x) ~ sqrt(2 / pi x) sin(x - pi / 4) give the tail in closed
+        // form. Otherwise (close to the axis) only the larger argument oscillates, and the tail
+        // is integrated by parts against the slowly varying remainder.
+        let lambda_tail = num_panels as f64 * panel_width;
+        let tail_rho = lambda_max_rho >= lambda_limit;
+        let tail_z = lambda_max_z >= lambda_limit;
+        if lambda_tail * rho.min(a) > TAIL_ARGUMENT_MIN {
+            let (w_rho, w_z) = weights(lambda_tail);
+            let amplitude = 1. / (PI * (rho * a).sqrt());
+            if tail_rho {
+                int_rho += amplitude
+                    * w_rho
+                    * (tail_sin(lambda_tail, rho - a) - tail_cos(lambda_tail, rho + a));
+            }
+            if tail_z {
+                int_z += amplitude
+                    * w_z
+                    * (tail_cos(lambda_tail, rho - a) + tail_sin(lambda_tail, rho + a));
+            }
+        } else if tail_rho || tail_z {
+            // The slowly varying remainder multiplying sqrt(lambda big) J(lambda big) in each
+            // integrand.
+            let big = rho.max(a);
+            let slow = |lambda: f64| -> (f64, f64) {
+                let (w_rho, w_z) = weights(lambda);
+                let envelope = (2. / (PI * lambda * big)).sqrt() / lambda;
+                let slow_rho = if rho > a {
+                    bessel::bessel_j0(lambda * a)
+                } else {
+                    bessel::bessel_j0_j1(lambda * rho).1
+                };
+                let slow_z = bessel::bessel_j0(lambda * rho.min(a));
+                (envelope * w_rho * slow_rho, envelope * w_z * slow_z)
+            };
+            // The Bessel function of the larger argument is J1 for Brho when rho > a.
+            let order_rho = if rho > a { 1 } else { 0 };
+
+            let step = 1e-3 * lambda_tail;
+            let (f_rho, f_z) = slow(lambda_tail);
+            let (f_rho_p, f_z_p) = slow(lambda_tail + step);
+            let (f_rho_m, f_z_m) = slow(lambda_tail - step);
+            let df_rho = (f_rho_p - f_rho_m) / (2. * step);
+            let df_z = (f_z_p - f_z_m) / (2. * step);
+
+            if tail_rho {
+                int_rho += tail_by_parts(lambda_tail, big, order_rho, f_rho, df_rho);
+            }
+            if tail_z {
+                int_z += tail_by_parts(lambda_tail, big, 0, f_z, df_z);
+            }
+        }
+
+        let b_rho = if inside {
+            2. * self.params.mu0_i_2 * int_rho
+        } else {
+            2. * self.params.mu0_i_2 * z.signum() * int_rho
+        };
+        let b_z = 2. * self.params.mu0_i_2 * int_z;
+
+        let z_star = if inside { z } else { z.signum() * d };
+        let b_phi = if rho == 0.0 {
+            0.0
+        } else {
+            -2.7975 * self.params.i_rho / rho * z_star / self.params.d
+        };
+
+        [b_rho, b_phi, b_z]
     }
 
     /// Return the parameters for the current sheet model.
     pub fn get_params(&self) -> HashMap<&str, f64> {
+        self.params.to_map()
+    }
+
+    /// Return the typed parameters of the current sheet model.
+    pub fn params(&self) -> &CurrentSheetParams {
+        &self.params
+    }
+}
+
+/// Parameters of a Connerney-type current sheet model. Distances are in planetary radii, the
+/// current constants are in nT and the angles are in radians. These can only be created through
+/// [`CurrentSheetParams::new`], the [`CurrentSheetParamsBuilder`], or by conversion from a
+/// `HashMap<String, f64>`, all of which validate the values.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CurrentSheetParams {
+    r_0: f64,
+    r_1: f64,
+    d: f64,
+    mu0_i_2: f64,
+    theta_d: f64,
+    phi_d: f64,
+    i_rho: f64,
+}
+
+impl CurrentSheetParams {
+    /// Create a validated set of parameters. Angles are in radians. Use `f64::NAN` for `r_1` to
+    /// model a current sheet without an outer edge.
+    pub fn new(
+        r_0: f64,
+        r_1: f64,
+        d: f64,
+        mu0_i_2: f64,
+        theta_d: f64,
+        phi_d: f64,
+        i_rho: f64,
+    ) -> Result<Self, CurrentSheetParamsError> {
+        let params = CurrentSheetParams {
+            r_0,
+            r_1,
+            d,
+            mu0_i_2,
+            theta_d,
+            phi_d,
+            i_rho,
+        };
+        params.validate()?;
+        Ok(params)
+    }
+
+    /// The parameters of the CON2020 model (Connerney et al., 2020).
+    pub fn con2020() -> Self {
+        CurrentSheetParams {
+            r_0: 7.8,
+            r_1: 51.4,
+            d: 3.6,
+            mu0_i_2: 139.6,
+            theta_d: 9.3 * PI / 180.,
+            phi_d: 204.2 * PI / 180.,
+            i_rho: 16.7,
+        }
+    }
+
+    /// Returns an empty [`CurrentSheetParamsBuilder`].
+    pub fn builder() -> CurrentSheetParamsBuilder {
+        CurrentSheetParamsBuilder::default()
+    }
+
+    /// Returns a [`CurrentSheetParamsBuilder`] initialized with these parameters, for example to
+    /// modify a few of the CON2020 values.
+    pub fn to_builder(&self) -> CurrentSheetParamsBuilder {
+        CurrentSheetParamsBuilder {
+            r_0: Some(self.r_0),
+            r_1: Some(self.r_1),
+            d: Some(self.d),
+            mu0_i_2: Some(self.mu0_i_2),
+            theta_d: Some(self.theta_d),
+            phi_d: Some(self.phi_d),
+            i_rho: Some(self.i_rho),
+        }
+    }
+
+    /// Inner radius of the current sheet.
+    pub fn r_0(&self) -> f64 {
+        self.r_0
+    }
+
+    /// Outer radius of the current sheet (NaN if the sheet has no outer edge).
+    pub fn r_1(&self) -> f64 {
+        self.r_1
+    }
+
+    /// Half-thickness of the current sheet.
+    pub fn d(&self) -> f64 {
+        self.d
+    }
+
+    /// Azimuthal current constant, mu0 I_0 / 2.
+    pub fn mu0_i_2(&self) -> f64 {
+        self.mu0_i_2
+    }
+
+    /// Tilt of the current sheet with respect to the spin axis, in radians.
+    pub fn theta_d(&self) -> f64 {
+        self.theta_d
+    }
+
+    /// System III (west) longitude of the current sheet tilt, in radians.
+    pub fn phi_d(&self) -> f64 {
+        self.phi_d
+    }
+
+    /// Radial current constant.
+    pub fn i_rho(&self) -> f64 {
+        self.i_rho
+    }
+
+    /// Return the parameters as a map from the parameter name to its value.
+    pub fn to_map(&self) -> HashMap<&'static str, f64> {
         HashMap::from([
             ("r_0", self.r_0),
             ("r_1", self.r_1),
@@ -154,37 +424,268 @@ impl CurrentSheetField {
             ("i_rho", self.i_rho),
         ])
     }
+
+    /// The parameters in the order of `PARAM_NAMES`.
+    pub(crate) fn to_array(self) -> [f64; 7] {
+        [
+            self.r_0,
+            self.r_1,
+            self.d,
+            self.mu0_i_2,
+            self.theta_d,
+            self.phi_d,
+            self.i_rho,
+        ]
+    }
+
+    /// Validated parameters from values in the order of `PARAM_NAMES`.
+    pub(crate) fn from_array(values: [f64; 7]) -> Result<Self, CurrentSheetParamsError> {
+        let [r_0, r_1, d, mu0_i_2, theta_d, phi_d, i_rho] = values;
+        CurrentSheetParams::new(r_0, r_1, d, mu0_i_2, theta_d, phi_d, i_rho)
+    }
+
+    fn validate(&self) -> Result<(), CurrentSheetParamsError> {
+        for (name, value) in [
+            ("r_0", self.r_0),
+            ("d", self.d),
+            ("mu0_i_2", self.mu0_i_2),
+            ("theta_d", self.theta_d),
+            ("phi_d", self.phi_d),
+            ("i_rho", self.i_rho),
+        ] {
+            if !value.is_finite() {
+                return Err(CurrentSheetParamsError::NotFinite(name, value));
+            }
+        }
+
+        for (name, value) in [("r_0", self.r_0), ("d", self.d)] {
+            if value <= 0. {
+                return Err(CurrentSheetParamsError::NotPositive(name, value));
+            }
+        }
+
+        // NaN is allowed for a sheet without an outer edge.
+        if self.r_1.is_infinite() {
+            return Err(CurrentSheetParamsError::NotFinite("r_1", self.r_1));
+        }
+        if self.r_1 <= self.r_0 {
+            return Err(CurrentSheetParamsError::InvalidRadii(self.r_0, self.r_1));
+        }
+
+        // Angles in degrees are by far the most common mistake, so reject values that cannot be
+        // radians for these angles.
+        if !(0. ..=PI).contains(&self.theta_d) {
+            return Err(CurrentSheetParamsError::AngleOutOfRange(
+                "theta_d",
+                self.theta_d,
+            ));
+        }
+        if self.phi_d.abs() > 2. * PI {
+            return Err(CurrentSheetParamsError::AngleOutOfRange(
+                "phi_d", self.phi_d,
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl TryFrom<&HashMap<String, f64>> for CurrentSheetParams {
+    type Error = CurrentSheetParamsError;
+
+    /// Convert from a map with exactly the keys `r_0`, `r_1`, `d`, `mu0_i_2`, `theta_d`, `phi_d`
+    /// and `i_rho`. Angles are in radians.
+    fn try_from(map: &HashMap<String, f64>) -> Result<Self, Self::Error> {
+        if let Some(key) = map.keys().find(|key| !PARAM_NAMES.contains(&key.as_str())) {
+            return Err(CurrentSheetParamsError::UnknownParameter(key.clone()));
+        }
+
+        let get = |name: &'static str| {
+            map.get(name)
+                .copied()
+                .ok_or(CurrentSheetParamsError::MissingParameter(name))
+        };
+
+        CurrentSheetParams::new(
+            get("r_0")?,
+            get("r_1")?,
+            get("d")?,
+            get("mu0_i_2")?,
+            get("theta_d")?,
+            get("phi_d")?,
+            get("i_rho")?,
+        )
+    }
+}
+
+/// Builder for [`CurrentSheetParams`]. All parameters must be set before calling
+/// [`CurrentSheetParamsBuilder::build`].
+#[derive(Clone, Debug, Default)]
+pub struct CurrentSheetParamsBuilder {
+    r_0: Option<f64>,
+    r_1: Option<f64>,
+    d: Option<f64>,
+    mu0_i_2: Option<f64>,
+    theta_d: Option<f64>,
+    phi_d: Option<f64>,
+    i_rho: Option<f64>,
+}
+
+impl CurrentSheetParamsBuilder {
+    /// Sets the inner radius of the current sheet.
+    pub fn r_0(mut self, r_0: f64) -> Self {
+        self.r_0 = Some(r_0);
+        self
+    }
+
+    /// Sets the outer radius of the current sheet. Use `f64::NAN` for a sheet without an outer
+    /// edge.
+    pub fn r_1(mut self, r_1: f64) -> Self {
+        self.r_1 = Some(r_1);
+        self
+    }
+
+    /// Sets the half-thickness of the current sheet.
+    pub fn d(mut self, d: f64) -> Self {
+        self.d = Some(d);
+        self
+    }
+
+    /// Sets the azimuthal current constant, mu0 I_0 / 2.
+    pub fn mu0_i_2(mut self, mu0_i_2: f64) -> Self {
+        self.mu0_i_2 = Some(mu0_i_2);
+        self
+    }
+
+    /// Sets the tilt of the current sheet with respect to the spin axis, in radians.
+    pub fn theta_d_radians(mut self, theta_d: f64) -> Self {
+        self.theta_d = Some(theta_d);
+        self
+    }
+
+    /// Sets the tilt of the current sheet with respect to the spin axis, in degrees.
+    pub fn theta_d_degrees(mut self, theta_d: f64) -> Self {
+        self.theta_d = Some(theta_d.to_radians());
+        self
+    }
+
+    /// Sets the System III (west) longitude of the current sheet tilt, in radians.
+    pub fn phi_d_radians(mut self, phi_d: f64) -> Self {
+        self.phi_d = Some(phi_d);
+        self
+    }
+
+    /// Sets the System III (west) longitude of the current sheet tilt, in degrees.
+    pub fn phi_d_degrees(mut self, phi_d: f64) -> Self {
+        self.phi_d = Some(phi_d.to_radians());
+        self
+    }
+
+    /// Sets `theta_d` and `phi_d` to the tilt and longitude of the dipole of an internal field
+    /// model, e.g. `CurrentSheetParams::con2020().to_builder().dipole(&jrm33.dipole()?)`.
+    pub fn dipole(mut self, dipole: &Dipole) -> Self {
+        self.theta_d = Some(dipole.tilt);
+        self.phi_d = Some(dipole.pole_longitude);
+        self
+    }
+
+    /// Sets the radial current constant.
+    pub fn i_rho(mut self, i_rho: f64) -> Self {
+        self.i_rho = Some(i_rho);
+        self
+    }
+
+    /// Consumes the builder and returns the validated [`CurrentSheetParams`].
+    pub fn build(self) -> Result<CurrentSheetParams, CurrentSheetParamsError> {
+        use CurrentSheetParamsError::MissingParameter;
+
+        CurrentSheetParams::new(
+            self.r_0.ok_or(MissingParameter("r_0"))?,
+            self.r_1.ok_or(MissingParameter("r_1"))?,
+            self.d.ok_or(MissingParameter("d"))?,
+            self.mu0_i_2.ok_or(MissingParameter("mu0_i_2"))?,
+            self.theta_d.ok_or(MissingParameter("theta_d"))?,
+            self.phi_d.ok_or(MissingParameter("phi_d"))?,
+            self.i_rho.ok_or(MissingParameter("i_rho"))?,
+        )
+    }
+}
+
+/// Errors that arise when validating [`CurrentSheetParams`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum CurrentSheetParamsError {
+    /// A required parameter was not given.
+    MissingParameter(&'static str),
+    /// A parameter name that is not part of the model was given.
+    UnknownParameter(String),
+    /// A parameter is NaN or infinite.
+    NotFinite(&'static str, f64),
+    /// A parameter that must be positive is zero or negative.
+    NotPositive(&'static str, f64),
+    /// The outer radius `r_1` is not larger than the inner radius `r_0`.
+    InvalidRadii(f64, f64),
+    /// An angle is outside the range expected for radians.
+    AngleOutOfRange(&'static str, f64),
 }
 
+impl std::fmt::Display for CurrentSheetParamsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingParameter(name) => write!(f, "Missing param - {name}"),
+            Self::UnknownParameter(name) => write!(
+                f,
+                "Unknown param - {name}. Allowed - {}",
+                PARAM_NAMES.join(", ")
+            ),
+            Self::NotFinite(name, value) => write!(f, "Param {name} must be finite, got {value}"),
+            Self::NotPositive(name, value) => {
+                write!(f, "Param {name} must be positive, got {value}")
+            }
+            Self::InvalidRadii(r_0, r_1) => write!(
+                f,
+                "Outer radius r_1 ({r_1}) must be larger than inner radius r_0 ({r_0})"
+            ),
+            Self::AngleOutOfRange(name, value) => write!(
+                f,
+                "Param {name} = {value} is out of range for an angle in radians. \
+                Was it given in degrees?"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CurrentSheetParamsError {}
+
 impl Field for CurrentSheetField {
-    fn calc_field(&self, r: f64, theta: f64, phi: f64) -> Array1<f64> {
-        let pos_xyz = convert::pos_rtp_to_xyz(&[r, theta, phi]);
-        let b_xyz = self.calc_field_xyz(pos_xyz[0], pos_xyz[1], pos_xyz[2]);
-        convert::vec_xyz_to_rtp(b_xyz.view(), &theta, &phi)
+    fn calc_field_array(&self, r: f64, theta: f64, phi: f64) -> [f64; 3] {
+        let pos_xyz = convert::pos_rtp_to_xyz_array([r, theta, phi]);
+        let b_xyz = self.calc_field_xyz_array(pos_xyz[0], pos_xyz[1], pos_xyz[2]);
+        convert::vec_xyz_to_rtp_array(b_xyz, theta, phi)
     }
 
-    fn calc_field_xyz(&self, x: f64, y: f64, z: f64) -> Array1<f64> {
+    fn calc_field_xyz_array(&self, x: f64, y: f64, z: f64) -> [f64; 3] {
         // Convert the input coordinates from IAU to MAG frame
-        let pos_xyz_mag = convert::vec_iau_to_mag(&[x, y, z], self.theta_d, self.phi_d);
+        let pos_xyz_mag =
+            convert::vec_iau_to_mag_array([x, y, z], self.params.theta_d, self.params.phi_d);
         let r_mag = (pos_xyz_mag[0].powi(2) + pos_xyz_mag[1].powi(2)).sqrt();
         let z_mag = pos_xyz_mag[2];
         let phi_mag = pos_xyz_mag[1].atan2(pos_xyz_mag[0]);
 
         // Perform calculation in IAU frame and get (Brho, Bz)_MAG
-        let mut b_mag_rpz = self._calc_field(r_mag, z_mag, self.r_0);
+        let mut b_mag_rpz = self._calc_field(r_mag, z_mag, self.params.r_0);
 
-        // Calculate outer field to subtract if self.r_1 is present
-        if !self.r_1.is_nan() {
-            let b_mag_rpz_outer = self._calc_field(r_mag, z_mag, self.r_1);
+        // Calculate outer field to subtract if self.params.r_1 is present
+        if !self.params.r_1.is_nan() {
+            let b_mag_rpz_outer = self._calc_field(r_mag, z_mag, self.params.r_1);
             // Only subtract the radial and Z components since Brho is the same.
-            b_mag_rpz[0] -= &b_mag_rpz_outer[0];
-            b_mag_rpz[2] -= &b_mag_rpz_outer[2];
+            b_mag_rpz[0] -= b_mag_rpz_outer[0];
+            b_mag_rpz[2] -= b_mag_rpz_outer[2];
         }
 
-        let b_mag = convert::vec_rpz_to_xyz(b_mag_rpz.view(), &phi_mag);
+        let b_mag = convert::vec_rpz_to_xyz_array(b_mag_rpz, phi_mag);
 
         // Convert (Bx, By, Bz)_MAG to (Bx, By, Bz)_IAU
-        convert::vec_mag_to_iau(b_mag.view(), self.theta_d, self.phi_d)
+        convert::vec_mag_to_iau_array(b_mag, self.params.theta_d, self.params.phi_d)
     }
 }
 
@@ -193,8 +694,117 @@ impl Field for CurrentSheetField {
 pub enum IntegrationType {
     /// Analytic integration using the Edwards et al. approximation.
     Analytic,
-    /// Numerical integration (currently unimplemented).
+    /// Numerical integration of the Bessel function (Hankel transform) form of the model.
     Integral,
+    /// Numerical integration close to the sheet and the disc edge, where the Edwards et al.
+    /// approximation is least accurate, and analytic integration everywhere else.
+    Hybrid(HybridCriterion),
+}
+
+/// Region in which [`IntegrationType::Hybrid`] uses the numerical integral. For each disc of
+/// radius `a` (`r_0` and `r_1`), the integral is used where both `|z| < z_factor * d` and
+/// `|rho - a| < rho_margin`.
+///
+/// The field is not continuous across the boundary of this region, since the analytic form is
+/// only approximate: it jumps by the error of the Edwards et al. approximation there. The
+/// adaptive field line tracer steps across these jumps by shrinking its step size, so traced
+/// field lines can have a small kink where they cross the boundary.
+#[derive(Clone, Copy, Debug)]
+pub struct HybridCriterion {
+    /// Multiple of the sheet half-thickness `d` above and below the sheet within which to
+    /// integrate.
+    pub z_factor: f64,
+    /// Radial distance from the disc edge within which to integrate.
+    pub rho_margin: f64,
+}
+
+impl HybridCriterion {
+    fn use_integral(&self, rho: f64, z: f64, a: f64, d: f64) -> bool {
+        z.abs() < self.z_factor * d && (rho - a).abs() < self.rho_margin
+    }
+}
+
+impl Default for HybridCriterion {
+    /// The switching region used by the community CON2020 codes, |z| < 1.5 d and |rho - a| < 2.
+    fn default() -> Self {
+        HybridCriterion {
+            z_factor: 1.5,
+            rho_margin: 2.0,
+        }
+    }
+}
+
+/// Upper limit of a Hankel integral whose integrand decays as exp(-decay * lambda), at most
+/// `lambda_limit`.
+fn integral_cutoff(decay: f64, lambda_limit: f64) -> f64 {
+    (40. / decay).min(lambda_limit)
+}
+
+/// The tail integral of f(lambda) sqrt(pi k lambda / 2) J_order(k lambda) from `lambda_0` to
+/// infinity for a slowly varying f, given f and its derivative at `lambda_0`.
+///
+/// Uses J_nu(x) ~ sqrt(2 / pi x) (cos(chi) - (4 nu^2 - 1) / 8x sin(chi)) with
+/// chi = x - nu pi / 2 - pi / 4, integrated by parts up to terms of order 1 / (k lambda_0)^2.
+fn tail_by_parts(lambda_0: f64, k: f64, order: u8, f: f64, df: f64) -> f64 {
+    let order = order as f64;
+    let (sin, cos) = (k * lambda_0 - (0.5 * order + 0.25) * PI).sin_cos();
+    let q = (4. * order * order - 1.) / (8. * k * lambda_0);
+    -f * sin / k - (df + q * k * f) * cos / (k * k)
+}
+
+/// The tail integral of cos(k lambda) / lambda^2 from `lambda_0` to infinity.
+fn tail_cos(lambda_0: f64, k: f64) -> f64 {
+    let k = k.abs();
+    let (si, _) = sine_cosine_integrals(lambda_0 * k);
+    (lambda_0 * k).cos() / lambda_0 - k * (0.5 * PI - si)
+}
+
+/// The tail integral of sin(k lambda) / lambda^2 from `lambda_0` to infinity.
+fn tail_sin(lambda_0: f64, k: f64) -> f64 {
+    if k == 0. {
+        return 0.;
+    }
+    let (_, ci) = sine_cosine_integrals(lambda_0 * k.abs());
+    k.signum() * ((lambda_0 * k.abs()).sin() / lambda_0 - k.abs() * ci)
+}
+
+/// The sine and cosine integrals Si(x) and Ci(x) for x > 0.
+fn sine_cosine_integrals(x: f64) -> (f64, f64) {
+    if x <= 20. {
+        // Ascending series.
+        let mut term = x;
+        let mut si = x;
+        let mut ci = 0.5772156649015329 + x.ln();
+        let mut k = 1.;
+        while term.abs() > 1e-17 {
+            // term = (-1)^k x^(2k + 1) / (2k + 1)!, shared between the two series.
+            term *= -x * x / ((2. * k) * (2. * k + 1.));
+            si += term / (2. * k + 1.);
+            ci += term * (2. * k + 1.) / (x * 2. * k);
+            k += 1.;
+        }
+        (si, ci)
+    } else {
+        // Asymptotic expansion in terms of the auxiliary functions f(x) and g(x).
+        let mut f = 1.;
+        let mut g = 1.;
+        let mut term_f: f64 = 1.;
+        let mut term_g: f64 = 1.;
+        for k in 1..(x as usize / 2) {
+            let k = k as f64;
+            term_f *= -(2. * k - 1.) * (2. * k) / (x * x);
+            term_g *= -(2. * k) * (2. * k + 1.) / (x * x);
+            f += term_f;
+            g += term_g;
+            if term_f.abs() < 1e-17 {
+                break;
+            }
+        }
+        let f = f / x;
+        let g = g / (x * x);
+        let (sinx, cosx) = x.sin_cos();
+        (0.5 * PI - f * cosx - g * sinx, f * sinx - g * cosx)
+    }
 }
 
 #[cfg(test)]
@@ -225,4 +835,235 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_calc_currentsheet_field_integral() {
+        use crate::currentsheet::{CurrentSheetField, IntegrationType};
+        use crate::field::Field;
+
+        let currentsheet_field =
+            CurrentSheetField::new("CON2020".to_string(), None, IntegrationType::Integral);
+
+        // (rho, z, a, Brho, Bz) for a single semi-infinite disc with d = 3.6 and mu0_i_2 = 139.6.
+        // Reference values are from an independent arbitrary-precision evaluation (mpmath) of
+        // the Bessel integrals for Brho and of the equivalent elliptic integral form for Bz,
+        // generated by iupitermag-py/scripts/currentsheet_integral_reference.py.
+        let val_test = [
+            (10.0, 5.0, 7.8, 42.6008171310719, 93.1021133749622),
+            (8.0, 1.0, 7.8, 10.8918401609077, 151.066570633307),
+            (7.8, -2.0, 7.8, -21.218906411041, 150.721802724882),
+            (7.8, 0.0, 7.8, 0.0, 157.634963842646),
+            (20.0, -6.0, 7.8, -34.5599273653159, 49.236554854907),
+            (5.0, 0.5, 7.8, 2.57710811602018, 135.721985022987),
+            (30.0, 4.0, 51.4, 0.681529924057352, 21.4607122155876),
+            (60.0, -1.5, 51.4, -5.81956841277055, 22.4648728379372),
+            (7.0, 3.0, 7.8, 25.0298713218423, 133.662347778463),
+            (0.0, 1.0, 7.8, 0.0, 123.88946848769),
+            (0.2, -2.0, 7.8, -0.299509619001523, 121.608342028879),
+            (0.5, 3.0, 51.4, 0.00548353334297946, 19.5063830901551),
+        ];
+
+        for (rho, z, a, b_rho, b_z) in val_test {
+            let val = currentsheet_field._calc_field_integral(rho, z, a);
+            assert!(
+                (val[0] - b_rho).abs() < 1e-5 && (val[2] - b_z).abs() < 1e-5,
+                "Currentsheet Integral Test Failed: \n Calculated {:?}, Expected {:?}",
+                val,
+                (b_rho, b_z)
+            );
+        }
+
+        let val = currentsheet_field.calc_field_xyz(20.2356, 1.31, -6.51);
+        let val_test = [-31.05383512355641, 1.5397290141107636, 23.532760422568828];
+
+        for (v1, v2) in val.iter().zip(val_test.iter()) {
+            assert!(
+                (v1 - v2).abs() < 1e-6,
+                "Currentsheet Field Test Failed: \n Calculated {:?}, Expected {:?}",
+                val,
+                val_test
+            );
+        }
+    }
+
+    #[test]
+    fn test_calc_currentsheet_field_hybrid() {
+        use crate::currentsheet::{CurrentSheetField, HybridCriterion, IntegrationType};
+
+        let analytic =
+            CurrentSheetField::new("CON2020".to_string(), None, IntegrationType::Analytic);
+        let integral =
+            CurrentSheetField::new("CON2020".to_string(), None, IntegrationType::Integral);
+        let hybrid = CurrentSheetField::new(
+            "CON2020".to_string(),
+            None,
+            IntegrationType::Hybrid(HybridCriterion::default()),
+        );
+
+        // Close to the inner edge and within 1.5 d of the sheet, the hybrid mode integrates.
+        for (rho, z) in [(8.0, 1.0), (8.0, 5.0)] {
+            let val = hybrid._calc_field(rho, z, 7.8);
+            let val_test = integral._calc_field(rho, z, 7.8);
+            assert!(
+                val == val_test,
+                "Currentsheet Hybrid Test Failed: \n Calculated {:?}, Expected {:?}",
+                val,
+                val_test
+            );
+        }
+
+        // Far from the disc edge, or far from the sheet, it uses the analytic form.
+        for (rho, z) in [(20.0, 1.0), (8.0, 6.0)] {
+            let val = hybrid._calc_field(rho, z, 7.8);
+            let val_test = analytic._calc_field(rho, z, 7.8);
+            assert!(
+                val == val_test,
+                "Currentsheet Hybrid Test Failed: \n Calculated {:?}, Expected {:?}",
+                val,
+                val_test
+            );
+        }
+    }
+
+    #[test]
+    fn test_currentsheet_params() {
+        use crate::currentsheet::{CurrentSheetParams, CurrentSheetParamsError};
+        use std::collections::HashMap;
+
+        let params = CurrentSheetParams::builder()
+            .r_0(7.8)
+            .r_1(51.4)
+            .d(3.6)
+            .mu0_i_2(139.6)
+            .theta_d_degrees(9.3)
+            .phi_d_degrees(204.2)
+            .i_rho(16.7)
+            .build()
+            .unwrap();
+
+        for (name, value) in CurrentSheetParams::con2020().to_map() {
+            assert!(
+                (params.to_map()[name] - value).abs() < 1e-12,
+                "Currentsheet Params Test Failed: \n Calculated {:?}, Expected {:?}",
+                params,
+                CurrentSheetParams::con2020()
+            );
+        }
+
+        let builder = CurrentSheetParams::con2020().to_builder();
+        let val_test = [
+            (
+                builder.clone().theta_d_radians(9.3).build(),
+                CurrentSheetParamsError::AngleOutOfRange("theta_d", 9.3),
+            ),
+            (
+                builder.clone().r_1(5.0).build(),
+                CurrentSheetParamsError::InvalidRadii(7.8, 5.0),
+            ),
+            (
+                builder.clone().d(-3.6).build(),
+                CurrentSheetParamsError::NotPositive("d", -3.6),
+            ),
+            (
+                CurrentSheetParams::builder().r_0(7.8).build(),
+                CurrentSheetParamsError::MissingParameter("r_1"),
+            ),
+        ];
+
+        for (val, err) in val_test {
+            assert!(
+                val == Err(err.clone()),
+                "Currentsheet Params Test Failed: \n Calculated {:?}, Expected {:?}",
+                val,
+                err
+            );
+        }
+
+        // A semi-infinite current sheet is allowed.
+        assert!(builder.r_1(f64::NAN).build().is_ok());
+
+        let mut map: HashMap<String, f64> = CurrentSheetParams::con2020()
+            .to_map()
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect();
+        assert!(CurrentSheetParams::try_from(&map) == Ok(CurrentSheetParams::con2020()));
+
+        let i_rho = map.remove("i_rho").unwrap();
+        map.insert("irho".to_string(), i_rho);
+        let val = CurrentSheetParams::try_from(&map);
+        assert!(
+            val == Err(CurrentSheetParamsError::UnknownParameter(
+                "irho".to_string()
+            )),
+            "Currentsheet Params Test Failed: \n Calculated {:?}",
+            val
+        );
+    }
+
+    #[test]
+    fn test_currentsheet_field_try_new() {
+        use crate::currentsheet::{CurrentSheetField, CurrentSheetParamsError, IntegrationType};
+        use crate::error::Error;
+        use std::collections::HashMap;
+
+        let val =
+            CurrentSheetField::try_new("CON2021".to_string(), None, IntegrationType::Analytic);
+        assert!(
+            matches!(val, Err(Error::UnknownModel { .. })),
+            "Currentsheet Field Error Test Failed"
+        );
+
+        let val = CurrentSheetField::try_new("Custom".to_string(), None, IntegrationType::Analytic);
+        assert!(
+            matches!(val, Err(Error::MissingInput(_))),
+            "Currentsheet Field Error Test Failed"
+        );
+
+        let params = HashMap::from([("r_0".to_string(), 7.8)]);
+        let val = CurrentSheetField::try_new(
+            "Custom".to_string(),
+            Some(params),
+            IntegrationType::Analytic,
+        );
+        assert!(
+            matches!(
+                val,
+                Err(Error::CurrentSheetParams(
+                    CurrentSheetParamsError::MissingParameter("r_1")
+                ))
+            ),
+            "Currentsheet Field Error Test Failed"
+        );
+    }
+
+    #[test]
+    fn test_currentsheet_jacobian() {
+        use crate::currentsheet::{CurrentSheetField, IntegrationType};
+        use crate::field::Field;
+
+        let currentsheet_field =
+            CurrentSheetField::new("CON2020".to_string(), None, IntegrationType::Integral);
+
+        // Away from the sheet the field is both divergence and curl free.
+        let val = currentsheet_field.calc_jacobian_xyz_array(20.2356, 1.31, -6.51);
+
+        let scale = val
+            .iter()
+            .flatten()
+            .fold(0., |acc: f64, v| acc.max(v.abs()));
+        let divergence = val[0][0] + val[1][1] + val[2][2];
+        let curl = [
+            val[2][1] - val[1][2],
+            val[0][2] - val[2][0],
+            val[1][0] - val[0][1],
+        ];
+
+        assert!(
+            divergence.abs() < 1e-5 * scale && curl.iter().all(|c| c.abs() < 1e-5 * scale),
+            "Currentsheet Jacobian Test Failed: \n Calculated divergence {:?}, curl {:?}",
+            divergence,
+            curl
+        );
+    }
 }
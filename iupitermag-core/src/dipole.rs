@@ -0,0 +1,172 @@
+use std::f64::consts::{PI, TAU};
+
+use ndarray::Array2;
+
+use crate::error::Error;
+use crate::internal::InternalField;
+use crate::models::JUPITER_RADIUS_KM;
+
+/// Vacuum permeability in T m / A.
+const MU_0: f64 = 4e-7 * PI;
+
+/// Properties of the dipole part (degree 1) of an internal field model, and of the eccentric
+/// dipole that best fits the degree 1 and 2 coefficients.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Dipole {
+    /// Equatorial surface field strength of the dipole, sqrt(g10^2 + g11^2 + h11^2), in nT.
+    pub b0: f64,
+    /// Magnitude of the dipole moment in A m^2.
+    pub moment: f64,
+    /// Angle between the dipole moment and the spin axis, in radians.
+    pub tilt: f64,
+    /// System III (west) longitude of the dipole moment, in radians in [0, 2 pi). This is the
+    /// same convention as `phi_d` of the current sheet.
+    pub pole_longitude: f64,
+    /// Position (x, y, z) of the eccentric dipole in the IAU frame, in planetary radii.
+    pub offset: [f64; 3],
+}
+
+impl InternalField {
+    /// Derive the dipole properties of the model from its degree 1 and 2 coefficients. The
+    /// moment uses the planet radius of the model metadata, or [`JUPITER_RADIUS_KM`] for custom
+    /// coefficients without metadata. Returns an error if the model has no dipole term.
+    ///
+    /// The eccentric dipole offset follows the Schmidt method, as given by e.g. Fraser-Smith
+    /// (1987), Centered and eccentric geomagnetic dipoles and their poles, Rev. Geophys. 25, 1-16.
+    pub fn dipole(&self) -> Result<Dipole, Error> {
+        let (g, h) = self.get_coefficients();
+        let coefficient = |x: &Array2<f64>, n: usize, m: usize| {
+            if n < x.nrows() { x[[n, m]] } else { 0. }
+        };
+
+        let (g10, g11, h11) = (
+            coefficient(&g, 1, 0),
+            coefficient(&g, 1, 1),
+            coefficient(&h, 1, 1),
+        );
+        let b0 = (g10 * g10 + g11 * g11 + h11 * h11).sqrt();
+        if b0 == 0. {
+            return Err(Error::InvalidCoefficients(
+                "the model has no dipole term".to_string(),
+            ));
+        }
+
+        let radius_m = 1e3
+            * self
+                .model_info()
+                .map_or(JUPITER_RADIUS_KM, |info| info.planet_radius_km);
+        let moment = 4. * PI * radius_m.powi(3) * b0 * 1e-9 / MU_0;
+
+        // The dipole moment points along (g11, h11, g10), and the east longitude of that
+        // direction is atan2(h11, g11).
+        let tilt = (g10 / b0).acos();
+        let pole_longitude = (-h11.atan2(g11)).rem_euclid(TAU);
+
+        let (g20, g21, g22) = (
+            coefficient(&g, 2, 0),
+            coefficient(&g, 2, 1),
+            coefficient(&g, 2, 2),
+        );
+        let (h21, h22) = (coefficient(&h, 2, 1), coefficient(&h, 2, 2));
+        let sqrt3 = 3_f64.sqrt();
+
+        let l0 = 2. * g10 * g20 + sqrt3 * (g11 * g21 + h11 * h21);
+        let l1 = -g11 * g20 + sqrt3 * (g10 * g21 + g11 * g22 + h11 * h22);
+        let l2 = -h11 * g20 + sqrt3 * (g10 * h21 - h11 * g22 + g11 * h22);
+        let e = (l0 * g10 + l1 * g11 + l2 * h11) / (4. * b0 * b0);
+
+        let offset = [
+            (l1 - g11 * e) / (3. * b0 * b0),
+            (l2 - h11 * e) / (3. * b0 * b0),
+            (l0 - g10 * e) / (3. * b0 * b0),
+        ];
+
+        Ok(Dipole {
+            b0,
+            moment,
+            tilt,
+            pole_longitude,
+            offset,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_dipole_jrm09() {
+        use crate::convert;
+        use crate::currentsheet::CurrentSheetParams;
+        use crate::internal::InternalField;
+
+        let dipole = InternalField::new("JRM09", None, None, None)
+            .dipole()
+            .unwrap();
+
+        // Connerney et al. (2018) give a tilt of 10.31 deg towards 196.61 deg west longitude.
+        assert!(
+            (dipole.tilt.to_degrees() - 10.31).abs() < 0.01
+                && (dipole.pole_longitude.to_degrees() - 196.61).abs() < 0.01,
+            "Dipole Test Failed: \n Calculated {:?}",
+            dipole
+        );
+        assert!(
+            (dipole.moment / 1.5e27 - 1.).abs() < 0.05,
+            "Dipole Moment Test Failed: \n Calculated {:?}",
+            dipole.moment
+        );
+
+        // The magnetic equator of a current sheet built from the dipole is perpendicular to
+        // the dipole moment.
+        let params = CurrentSheetParams::con2020()
+            .to_builder()
+            .dipole(&dipole)
+            .build()
+            .unwrap();
+        let val = convert::vec_mag_to_iau_array([0., 0., 1.], params.theta_d(), params.phi_d());
+        let val_test = [
+            -71498.3 / dipole.b0,
+            21330.5 / dipole.b0,
+            410244.7 / dipole.b0,
+        ];
+        for i in 0..3 {
+            assert!(
+                (val[i] - val_test[i]).abs() < 1e-12,
+                "Dipole Current Sheet Test Failed: \n Calculated {:?}, Expected {:?}",
+                val,
+                val_test
+            );
+        }
+    }
+
+    #[test]
+    fn test_dipole_offset() {
+        use crate::internal::InternalField;
+        use ndarray::Array2;
+
+        // A pure axial dipole displaced along z by dz has g20 = 2 g10 dz to first order.
+        let (g10, dz) = (1e5, 0.01);
+        let mut g = Array2::<f64>::zeros((3, 3));
+        g[[1, 0]] = g10;
+        g[[2, 0]] = 2. * g10 * dz;
+        let field = InternalField::new("Custom", Some(g), Some(Array2::zeros((3, 3))), None);
+
+        let dipole = field.dipole().unwrap();
+        assert!(
+            dipole.offset[0].abs() < 1e-12
+                && dipole.offset[1].abs() < 1e-12
+                && (dipole.offset[2] - dz).abs() < 1e-12,
+            "Dipole Offset Test Failed: \n Calculated {:?}, Expected {:?}",
+            dipole.offset,
+            [0., 0., dz]
+        );
+
+        let field = InternalField::new(
+            "Custom",
+            Some(Array2::zeros((2, 2))),
+            Some(Array2::zeros((2, 2))),
+            None,
+        );
+        assert!(field.dipole().is_err(), "Dipole Test Failed");
+    }
+}
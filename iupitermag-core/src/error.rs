@@ -0,0 +1,91 @@
+use crate::currentsheet::CurrentSheetParamsError;
+use lazyivy::error::BuilderError;
+
+/// Errors returned by the fallible constructors and methods in this crate.
+#[derive(Debug)]
+pub enum Error {
+    /// The requested named model is not known.
+    UnknownModel {
+        /// The requested model name.
+        name: String,
+        /// The model names that are supported.
+        supported: String,
+    },
+    /// The requested model is known, but its coefficients are not bundled with this crate.
+    ModelNotBundled(String),
+    /// Coefficients or parameters that are required for a custom model were not given.
+    MissingInput(&'static str),
+    /// The given spherical harmonic coefficients are inconsistent.
+    InvalidCoefficients(String),
+    /// A matrix that should be a rotation is not one.
+    InvalidRotation(String),
+    /// A coefficient file could not be parsed.
+    Parse(String),
+    /// Reading or writing a file failed.
+    Io(std::io::Error),
+    /// The current sheet parameters failed validation.
+    CurrentSheetParams(CurrentSheetParamsError),
+    /// The field line integrator could not be built.
+    Integrator(BuilderError),
+    /// The field line tracer settings are invalid.
+    InvalidTraceConfig(String),
+    /// A least-squares inversion could not be solved.
+    Inversion(String),
+    /// The settings of a current sheet fit are invalid, e.g. reversed bounds.
+    Fit(String),
+    /// Input arrays do not have the expected or mutually consistent shapes.
+    InvalidShape(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownModel { name, supported } => {
+                write!(f, "Unknown field_type: {name}. Supported ({supported})")
+            }
+            Self::ModelNotBundled(name) => write!(
+                f,
+                "Coefficients for {name} are not bundled with iupitermag. Use custom coefficients instead."
+            ),
+            Self::MissingInput(message) => write!(f, "{message}"),
+            Self::InvalidCoefficients(message) => write!(f, "Invalid coefficients: {message}"),
+            Self::InvalidRotation(message) => write!(f, "Invalid rotation matrix: {message}"),
+            Self::Parse(message) => write!(f, "Could not parse coefficients: {message}"),
+            Self::Io(e) => write!(f, "I/O error: {e}"),
+            Self::CurrentSheetParams(e) => write!(f, "Invalid current sheet params - {e}"),
+            Self::Integrator(e) => write!(f, "Could not build field line integrator: {e}"),
+            Self::InvalidTraceConfig(message) => write!(f, "Invalid trace config: {message}"),
+            Self::Inversion(message) => write!(f, "Inversion failed: {message}"),
+            Self::Fit(message) => write!(f, "Invalid current sheet fit: {message}"),
+            Self::InvalidShape(message) => write!(f, "Invalid array shape: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::CurrentSheetParams(e) => Some(e),
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<CurrentSheetParamsError> for Error {
+    fn from(e: CurrentSheetParamsError) -> Self {
+        Self::CurrentSheetParams(e)
+    }
+}
+
+impl From<BuilderError> for Error {
+    fn from(e: BuilderError) -> Self {
+        Self::Integrator(e)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
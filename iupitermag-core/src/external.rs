@@ -0,0 +1,154 @@
+use crate::error::Error;
+use crate::field::Field;
+use crate::internal::check_coefficient_shapes;
+use crate::legendre::{LEGENDRE_WORKSPACE, LegendreWorkspace};
+use ndarray::{ArcArray2, Array2, s};
+
+/// Struct to characterize the external (magnetospheric or induced) part of a spherical harmonic
+/// field model, using Schmidt coefficients q and s. The scalar potential is
+///
+/// V = a sum_n (r / a)^n sum_m P_n^m(cos theta) (q_n^m cos(m phi) + s_n^m sin(m phi)),
+///
+/// so the field of degree n grows as r^(n - 1), unlike the (a / r)^(n + 2) of
+/// [`crate::internal::InternalField`].
+#[derive(Clone)]
+pub struct ExternalHarmonicField {
+    // Note: Using ArcArray to derive Sync
+    q: ArcArray2<f64>,
+    s: ArcArray2<f64>,
+    degree: usize,
+}
+
+impl ExternalHarmonicField {
+    /// Instantiate a new `ExternalHarmonicField` from the q and s coefficients in nT. Panics if
+    /// the inputs are invalid, see [`ExternalHarmonicField::try_new`] for the fallible version.
+    pub fn new(q: Array2<f64>, s: Array2<f64>, degree: Option<usize>) -> Self {
+        Self::try_new(q, s, degree).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Instantiate a new `ExternalHarmonicField` from the q and s coefficients in nT, where
+    /// `q[[n, m]]` is q_n^m. The coefficients are truncated to `degree` if it is given. Returns an
+    /// error if q and s are not square arrays of the same shape.
+    pub fn try_new(q: Array2<f64>, s: Array2<f64>, degree: Option<usize>) -> Result<Self, Error> {
+        check_coefficient_shapes(&q, &s)?;
+
+        let degree = degree.unwrap_or(usize::MAX).min(q.nrows() - 1);
+        let size = degree + 1;
+
+        Ok(ExternalHarmonicField {
+            q: q.slice(s![..size, ..size]).to_shared(),
+            s: s.slice(s![..size, ..size]).to_shared(),
+            degree,
+        })
+    }
+
+    /// Get the Schmidt coefficients (q, s) of this external field model.
+    pub fn get_coefficients(&self) -> (Array2<f64>, Array2<f64>) {
+        (self.q.to_owned(), self.s.to_owned())
+    }
+
+    /// Sum the field (Br, Btheta, Bphi) over all degrees and orders, using P / sin(theta) for
+    /// Bphi so that it has the correct limit on the poles.
+    fn sum_field(&self, workspace: &LegendreWorkspace, r: f64, phi: f64) -> [f64; 3] {
+        let mut b_r: f64 = 0.;
+        let mut b_theta: f64 = 0.;
+        let mut b_phi: f64 = 0.;
+
+        let (p, dp, p_over_sin) = (workspace.p(), workspace.dp(), workspace.p_over_sin());
+
+        // r^(n - 1), updated incrementally. Degree 0 is a constant potential without a field.
+        let mut r_pow = 1.;
+
+        for i in 1..self.degree + 1 {
+            let (q_i, s_i) = (self.q.row(i), self.s.row(i));
+            let (p_i, dp_i, p_over_sin_i) = (p.row(i), dp.row(i), p_over_sin.row(i));
+
+            for j in 0..i + 1 {
+                let (sin_mphi, cos_mphi) = (j as f64 * phi).sin_cos();
+                let qs_cos = q_i[j] * cos_mphi + s_i[j] * sin_mphi;
+
+                b_r -= r_pow * i as f64 * p_i[j] * qs_cos;
+                b_theta -= r_pow * dp_i[j] * qs_cos;
+
+                if j > 0 {
+                    b_phi += r_pow
+                        * p_over_sin_i[j]
+                        * j as f64
+                        * (q_i[j] * sin_mphi - s_i[j] * cos_mphi);
+                }
+            }
+
+            r_pow *= r;
+        }
+
+        [b_r, b_theta, b_phi]
+    }
+}
+
+impl Field for ExternalHarmonicField {
+    fn calc_field_array(&self, r: f64, theta: f64, phi: f64) -> [f64; 3] {
+        LEGENDRE_WORKSPACE.with_borrow_mut(|workspace| {
+            workspace.compute(theta, self.degree);
+            self.sum_field(workspace, r, phi)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_external_field_uniform() {
+        use crate::external::ExternalHarmonicField;
+        use crate::field::Field;
+        use ndarray::Array2;
+
+        // q10 alone is a uniform field of -q10 along z.
+        let mut q = Array2::<f64>::zeros((2, 2));
+        q[[1, 0]] = 10.;
+        let field = ExternalHarmonicField::new(q, Array2::zeros((2, 2)), None);
+
+        for (x, y, z) in [(1., 2., 3.), (-20., 5., 0.5)] {
+            let val = field.calc_field_xyz_array(x, y, z);
+            let val_test = [0., 0., -10.];
+            for i in 0..3 {
+                assert!(
+                    (val[i] - val_test[i]).abs() < 1e-12,
+                    "External Field Test Failed: \n Calculated {:?}, Expected {:?}",
+                    val,
+                    val_test
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_external_field_jacobian() {
+        use crate::external::ExternalHarmonicField;
+        use crate::field::Field;
+        use ndarray::array;
+
+        let q = array![[0., 0., 0.], [-12., 3., 0.], [1.5, -0.4, 0.8]];
+        let s = array![[0., 0., 0.], [0., -2., 0.], [0., 0.7, -0.3]];
+        let field = ExternalHarmonicField::new(q, s, None);
+
+        // A potential field is divergence and curl free.
+        let val = field.calc_jacobian_xyz_array(3., -7., 2.);
+        let scale = val
+            .iter()
+            .flatten()
+            .fold(0., |acc: f64, v| acc.max(v.abs()));
+        let divergence = val[0][0] + val[1][1] + val[2][2];
+        let curl = [
+            val[2][1] - val[1][2],
+            val[0][2] - val[2][0],
+            val[1][0] - val[0][1],
+        ];
+
+        assert!(
+            divergence.abs() < 1e-8 * scale && curl.iter().all(|x| x.abs() < 1e-8 * scale),
+            "External Field Jacobian Test Failed: \n Calculated {:?}, {:?}",
+            divergence,
+            curl
+        );
+    }
+}
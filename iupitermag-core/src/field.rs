@@ -1,17 +1,40 @@
 use crate::convert;
-use ndarray::{Array1, Array2, ArrayView2, Zip};
+use ndarray::{Array1, Array2, Array3, ArrayView1, ArrayView2, Zip};
 
 /// Contains methods that are shared between all types of magnetic field models.
 pub trait Field {
+    /// Calculate the field (Br, Btheta, Bphi) at a point at spherical coordinates (r, theta, phi),
+    /// without allocating. All other methods of this trait are built on top of this one.
+    fn calc_field_array(&self, r: f64, theta: f64, phi: f64) -> [f64; 3];
+
+    /// Same as [`Field::calc_field_xyz`], but returns a fixed-size array and does not allocate.
+    fn calc_field_xyz_array(&self, x: f64, y: f64, z: f64) -> [f64; 3] {
+        let pos_rtp = convert::pos_xyz_to_rtp_array([x, y, z]);
+        let b_rtp = self.calc_field_array(pos_rtp[0], pos_rtp[1], pos_rtp[2]);
+        convert::vec_rtp_to_xyz_array(b_rtp, pos_rtp[1], pos_rtp[2])
+    }
+
     /// Calculate the field (Br, Btheta, Bphi) at a point at spherical coordinates (r, theta, phi).
-    fn calc_field(&self, r: f64, theta: f64, phi: f64) -> Array1<f64>;
+    fn calc_field(&self, r: f64, theta: f64, phi: f64) -> Array1<f64> {
+        Array1::from_vec(self.calc_field_array(r, theta, phi).to_vec())
+    }
 
     /// Return the field calculated at a point (x, y, z) in cartesian coordinates
     /// (Bx, By, Bz).
     fn calc_field_xyz(&self, x: f64, y: f64, z: f64) -> Array1<f64> {
-        let pos_rtp = convert::pos_xyz_to_rtp(&[x, y, z]);
-        let b_rtp = self.calc_field(pos_rtp[0], pos_rtp[1], pos_rtp[2]);
-        convert::vec_rtp_to_xyz(b_rtp.view(), &pos_rtp[1], &pos_rtp[2])
+        Array1::from_vec(self.calc_field_xyz_array(x, y, z).to_vec())
+    }
+
+    /// Calculate the gradient tensor of the field at a point (x, y, z) in cartesian coordinates,
+    /// returned as J[i][j] = dB_i / dx_j. The default implementation uses a fourth-order central
+    /// finite difference of [`Field::calc_field_xyz_array`], see [`numerical_jacobian_xyz`].
+    fn calc_jacobian_xyz_array(&self, x: f64, y: f64, z: f64) -> [[f64; 3]; 3] {
+        numerical_jacobian_xyz(self, x, y, z)
+    }
+
+    /// Return the gradient tensor dB_i / dx_j at a point (x, y, z) as a (3, 3) array.
+    fn calc_jacobian_xyz(&self, x: f64, y: f64, z: f64) -> Array2<f64> {
+        Array2::from(self.calc_jacobian_xyz_array(x, y, z).to_vec())
     }
 
     /// Calculate the field at a collection of points (`positions`) where `positions` is of shape
@@ -22,7 +45,7 @@ pub trait Field {
         Zip::from(result.rows_mut())
             .and(positions.rows())
             .for_each(|mut x, y| {
-                x.assign(&self.calc_field(y[0], y[1], y[2]));
+                x.assign(&ArrayView1::from(&self.calc_field_array(y[0], y[1], y[2])));
             });
 
         result
@@ -38,7 +61,7 @@ pub trait Field {
         Zip::from(result.rows_mut())
             .and(positions.rows())
             .par_for_each(|mut x, y| {
-                x.assign(&self.calc_field(y[0], y[1], y[2]));
+                x.assign(&ArrayView1::from(&self.calc_field_array(y[0], y[1], y[2])));
             });
 
         result
@@ -52,7 +75,9 @@ pub trait Field {
         Zip::from(result.rows_mut())
             .and(positions.rows())
             .for_each(|mut x, y| {
-                x.assign(&self.calc_field_xyz(y[0], y[1], y[2]));
+                x.assign(&ArrayView1::from(
+                    &self.calc_field_xyz_array(y[0], y[1], y[2]),
+                ));
             });
 
         result
@@ -68,9 +93,225 @@ pub trait Field {
         Zip::from(result.rows_mut())
             .and(positions.rows())
             .par_for_each(|mut x, y| {
-                x.assign(&self.calc_field_xyz(y[0], y[1], y[2]));
+                x.assign(&ArrayView1::from(
+                    &self.calc_field_xyz_array(y[0], y[1], y[2]),
+                ));
             });
 
         result
     }
+
+    /// Calculate the gradient tensor at a collection of points (`positions`) of shape (N, 3) with
+    /// the three indices indicating (X, Y, Z). Returns an array of shape (N, 3, 3) where
+    /// [n, i, j] is dB_i / dx_j at point n.
+    fn map_calc_jacobian_xyz(&self, positions: ArrayView2<f64>) -> Array3<f64> {
+        let mut result = Array3::<f64>::zeros((positions.nrows(), 3, 3));
+
+        Zip::from(result.outer_iter_mut())
+            .and(positions.rows())
+            .for_each(|mut x, y| {
+                x.assign(&Array2::from(
+                    self.calc_jacobian_xyz_array(y[0], y[1], y[2]).to_vec(),
+                ));
+            });
+
+        result
+    }
+
+    /// Similar to [`Field::map_calc_jacobian_xyz`], but uses Rayon for parallelizing.
+    fn parmap_calc_jacobian_xyz(&self, positions: ArrayView2<f64>) -> Array3<f64>
+    where
+        Self: Sync,
+    {
+        let mut result = Array3::<f64>::zeros((positions.nrows(), 3, 3));
+
+        Zip::from(result.outer_iter_mut())
+            .and(positions.rows())
+            .par_for_each(|mut x, y| {
+                x.assign(&Array2::from(
+                    self.calc_jacobian_xyz_array(y[0], y[1], y[2]).to_vec(),
+                ));
+            });
+
+        result
+    }
+}
+
+/// Fourth-order central finite difference approximation of the gradient tensor
+/// J[i][j] = dB_i / dx_j of any field at (x, y, z). The step is proportional to the distance
+/// from the origin, which keeps the truncation error small relative to the scale over which
+/// planetary fields vary.
+pub fn numerical_jacobian_xyz<F: Field + ?Sized>(
+    field: &F,
+    x: f64,
+    y: f64,
+    z: f64,
+) -> [[f64; 3]; 3] {
+    let pos = [x, y, z];
+    let r = (x * x + y * y + z * z).sqrt();
+    let step = (1e-3 * r).max(1e-6);
+
+    let mut jacobian = [[0.; 3]; 3];
+
+    for j in 0..3 {
+        let b_at = |offset: f64| {
+            let mut p = pos;
+            p[j] += offset * step;
+            field.calc_field_xyz_array(p[0], p[1], p[2])
+        };
+        let (b_p1, b_m1) = (b_at(1.), b_at(-1.));
+        let (b_p2, b_m2) = (b_at(2.), b_at(-2.));
+
+        for i in 0..3 {
+            jacobian[i][j] = (8. * (b_p1[i] - b_m1[i]) - (b_p2[i] - b_m2[i])) / (12. * step);
+        }
+    }
+
+    jacobian
+}
+
+/// A source of a [`CompositeField`] and the factor its field is scaled by.
+type ScaledField = (Box<dyn Field + Send + Sync>, f64);
+
+/// The superposition of any number of fields, each scaled by a factor, e.g. an internal field
+/// model plus a current sheet:
+///
+/// ```
+/// # use iupitermag::currentsheet::{CurrentSheetField, CurrentSheetParams, IntegrationType};
+/// # use iupitermag::field::{CompositeField, Field};
+/// # use iupitermag::internal::InternalField;
+/// let field = CompositeField::new()
+///     .with(InternalField::new("JRM33", None, None, Some(13)))
+///     .with_scaled(
+///         CurrentSheetField::from_params(CurrentSheetParams::con2020(), IntegrationType::Analytic),
+///         0.5,
+///     );
+/// let b = field.calc_field_xyz(-10., 2., 3.);
+/// ```
+///
+/// Each source computes its field in whichever coordinates it is defined in, so the sum is taken
+/// separately for the spherical, cartesian and gradient methods.
+#[derive(Default)]
+pub struct CompositeField {
+    sources: Vec<ScaledField>,
+}
+
+impl CompositeField {
+    /// Create a composite field without any sources, which evaluates to zero everywhere.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `field` with a scale factor of 1.
+    pub fn with(self, field: impl Field + Send + Sync + 'static) -> Self {
+        self.with_scaled(field, 1.)
+    }
+
+    /// Add `field` scaled by `scale`.
+    pub fn with_scaled(mut self, field: impl Field + Send + Sync + 'static, scale: f64) -> Self {
+        self.push(Box::new(field), scale);
+        self
+    }
+
+    /// Add a boxed `field` scaled by `scale`.
+    pub fn push(&mut self, field: Box<dyn Field + Send + Sync>, scale: f64) {
+        self.sources.push((field, scale));
+    }
+
+    /// Number of sources.
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+
+    /// Whether there are no sources.
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+}
+
+impl Field for CompositeField {
+    fn calc_field_array(&self, r: f64, theta: f64, phi: f64) -> [f64; 3] {
+        let mut b = [0.; 3];
+        for (field, scale) in &self.sources {
+            let b_source = field.calc_field_array(r, theta, phi);
+            (0..3).for_each(|i| b[i] += scale * b_source[i]);
+        }
+        b
+    }
+
+    fn calc_field_xyz_array(&self, x: f64, y: f64, z: f64) -> [f64; 3] {
+        let mut b = [0.; 3];
+        for (field, scale) in &self.sources {
+            let b_source = field.calc_field_xyz_array(x, y, z);
+            (0..3).for_each(|i| b[i] += scale * b_source[i]);
+        }
+        b
+    }
+
+    fn calc_jacobian_xyz_array(&self, x: f64, y: f64, z: f64) -> [[f64; 3]; 3] {
+        let mut jacobian = [[0.; 3]; 3];
+        for (field, scale) in &self.sources {
+            let jacobian_source = field.calc_jacobian_xyz_array(x, y, z);
+            for i in 0..3 {
+                (0..3).for_each(|j| jacobian[i][j] += scale * jacobian_source[i][j]);
+            }
+        }
+        jacobian
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_composite_field() {
+        use crate::currentsheet::{CurrentSheetField, CurrentSheetParams, IntegrationType};
+        use crate::field::{CompositeField, Field};
+        use crate::internal::InternalField;
+
+        let internal_field = InternalField::new("JRM33", None, None, Some(10));
+        let currentsheet_field = CurrentSheetField::from_params(
+            CurrentSheetParams::con2020(),
+            IntegrationType::Analytic,
+        );
+
+        let field = CompositeField::new()
+            .with(internal_field.clone())
+            .with_scaled(currentsheet_field.clone(), 2.)
+            .with_scaled(internal_field.clone(), -1.);
+
+        let (x, y, z) = (-10., 2., 3.);
+        let b_test = currentsheet_field
+            .calc_field_xyz_array(x, y, z)
+            .map(|b| 2. * b);
+        let jacobian_test = currentsheet_field
+            .calc_jacobian_xyz_array(x, y, z)
+            .map(|row| row.map(|b| 2. * b));
+
+        let b = field.calc_field_xyz_array(x, y, z);
+        let jacobian = field.calc_jacobian_xyz_array(x, y, z);
+        for i in 0..3 {
+            assert!(
+                (b[i] - b_test[i]).abs() < 1e-9
+                    && (0..3).all(|j| (jacobian[i][j] - jacobian_test[i][j]).abs() < 1e-9),
+                "Composite Field Test Failed: \n Calculated {:?}, Expected {:?}",
+                (b, jacobian),
+                (b_test, jacobian_test)
+            );
+        }
+
+        let pos_rtp = crate::convert::pos_xyz_to_rtp_array([x, y, z]);
+        let b_rtp = field.calc_field_array(pos_rtp[0], pos_rtp[1], pos_rtp[2]);
+        let b_rtp_test = crate::convert::vec_xyz_to_rtp_array(b_test, pos_rtp[1], pos_rtp[2]);
+        for i in 0..3 {
+            assert!(
+                (b_rtp[i] - b_rtp_test[i]).abs() < 1e-9,
+                "Composite Field Test Failed: \n Calculated {:?}, Expected {:?}",
+                b_rtp,
+                b_rtp_test
+            );
+        }
+
+        assert_eq!(field.len(), 3);
+        assert!(CompositeField::new().calc_field_array(2., 1., 0.) == [0.; 3]);
+    }
 }
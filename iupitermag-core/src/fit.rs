@@ -0,0 +1,414 @@
+use std::collections::HashMap;
+
+use ndarray::{Array1, Array2, ArrayView2};
+
+use crate::currentsheet::{
+    CurrentSheetField, CurrentSheetParams, CurrentSheetParamsError, IntegrationType, PARAM_NAMES,
+};
+use crate::error::Error;
+use crate::field::Field;
+use crate::inversion::{cholesky, cholesky_inverse, cholesky_solve};
+
+/// Relative step of the central differences for the parameter Jacobian.
+const JACOBIAN_STEP: f64 = 1e-6;
+
+/// Largest damping factor of the Levenberg-Marquardt iteration before it gives up on a step.
+const MAX_DAMPING: f64 = 1e16;
+
+/// Result of [`CurrentSheetFit::solve`].
+#[derive(Clone)]
+pub struct CurrentSheetFitResult {
+    /// The best-fit current sheet field, using the analytic integration.
+    pub field: CurrentSheetField,
+    /// Names of the fitted (not frozen) parameters, in the order of the covariance.
+    pub param_names: Vec<&'static str>,
+    /// Covariance of the fitted parameters, scaled by the residual variance. Parameters that the
+    /// observations do not constrain make the matrix singular, in which case it is all NaN.
+    pub covariance: Array2<f64>,
+    /// Residuals of (Br, Btheta, Bphi) in nT, observed minus modelled, of shape (N, 3).
+    pub residuals: Array2<f64>,
+    /// Root mean square of the residuals in nT.
+    pub rms: f64,
+    /// Number of Levenberg-Marquardt iterations.
+    pub iterations: usize,
+    /// Whether the relative decrease of the misfit fell below the tolerance before the maximum
+    /// number of iterations.
+    pub converged: bool,
+}
+
+/// Levenberg-Marquardt fit of the current sheet parameters to magnetic field observations, with
+/// a fixed field (usually an [`crate::internal::InternalField`]) accounting for the rest of the
+/// observed field. Parameters are named as in [`CurrentSheetParams::to_map`], e.g.
+///
+/// ```
+/// # use iupitermag::currentsheet::{CurrentSheetField, CurrentSheetParams, IntegrationType};
+/// # use iupitermag::field::Field;
+/// # use iupitermag::fit::CurrentSheetFit;
+/// # use iupitermag::internal::InternalField;
+/// # use ndarray::Array2;
+/// # let internal_field = InternalField::new("JRM09", None, None, None);
+/// # let positions = Array2::from_shape_fn((100, 3), |(i, j)| match j {
+/// #     0 => 8. + (i % 20) as f64,
+/// #     1 => 1.2 + 0.007 * i as f64,
+/// #     _ => 0.61 * i as f64,
+/// # });
+/// # let observed = internal_field.map_calc_field(positions.view())
+/// #     + CurrentSheetField::from_params(CurrentSheetParams::con2020(), IntegrationType::Analytic)
+/// #         .map_calc_field(positions.view());
+/// let result = CurrentSheetFit::new(&internal_field, CurrentSheetParams::con2020())
+///     .freeze("r_1")
+///     .bounds("d", 2., 5.)
+///     .solve(positions.view(), observed.view())
+///     .unwrap();
+/// assert!(result.rms < 1e-6);
+/// ```
+///
+/// The parameter Jacobian is computed by central differences of the analytic field.
+#[derive(Clone)]
+pub struct CurrentSheetFit<'a> {
+    fixed_field: &'a (dyn Field + Sync),
+    initial: CurrentSheetParams,
+    bounds: HashMap<String, (f64, f64)>,
+    frozen: Vec<String>,
+    max_iterations: usize,
+    tolerance: f64,
+}
+
+impl<'a> CurrentSheetFit<'a> {
+    /// Set up a fit starting from `initial`, with all parameters free and unbounded (apart from
+    /// the validation of [`CurrentSheetParams`]). A sheet without an outer edge keeps `r_1` fixed.
+    pub fn new(fixed_field: &'a (dyn Field + Sync), initial: CurrentSheetParams) -> Self {
+        CurrentSheetFit {
+            fixed_field,
+            initial,
+            bounds: HashMap::new(),
+            frozen: Vec::new(),
+            max_iterations: 100,
+            tolerance: 1e-10,
+        }
+    }
+
+    /// Restrict the parameter `name` to `lower..=upper`.
+    pub fn bounds(mut self, name: &str, lower: f64, upper: f64) -> Self {
+        self.bounds.insert(name.to_string(), (lower, upper));
+        self
+    }
+
+    /// Keep the parameter `name` at its initial value.
+    pub fn freeze(mut self, name: &str) -> Self {
+        self.frozen.push(name.to_string());
+        self
+    }
+
+    /// Set the maximum number of iterations (default 100).
+    pub fn max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Set the relative decrease of the misfit below which the fit has converged (default
+    /// 1e-10).
+    pub fn tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Fit the observed field `observed` (Br, Btheta, Bphi in nT, shape (N, 3)) at `positions`
+    /// ((r, theta, phi), shape (N, 3)). Returns an error if the shapes are inconsistent, there
+    /// are no observations, a parameter name is unknown, a lower bound is above its upper bound or either is NaN, or
+    /// the initial parameters are invalid.
+    pub fn solve(
+        &self,
+        positions: ArrayView2<f64>,
+        observed: ArrayView2<f64>,
+    ) -> Result<CurrentSheetFitResult, Error> {
+        if positions.ncols() != 3 || observed.dim() != positions.dim() {
+            return Err(Error::InvalidShape(format!(
+                "positions and observations must both have shape (N, 3), got {:?} and {:?}",
+                positions.shape(),
+                observed.shape()
+            )));
+        }
+        if positions.nrows() == 0 {
+            return Err(Error::InvalidShape(
+                "at least one observation is needed".to_string(),
+            ));
+        }
+        if let Some(name) = self
+            .bounds
+            .keys()
+            .chain(self.frozen.iter())
+            .find(|name| !PARAM_NAMES.contains(&name.as_str()))
+        {
+            return Err(CurrentSheetParamsError::UnknownParameter(name.clone()).into());
+        }
+        if let Some((name, (low, high))) = self
+            .bounds
+            .iter()
+            .find(|(_, (low, high))| low.is_nan() || high.is_nan() || low > high)
+        {
+            return Err(Error::Fit(format!(
+                "invalid bounds {low}..={high} for {name}"
+            )));
+        }
+
+        let mut values = self.initial.to_array();
+        let (mut lower, mut upper) = ([f64::NEG_INFINITY; 7], [f64::INFINITY; 7]);
+        for (i, name) in PARAM_NAMES.iter().enumerate() {
+            if let Some(&(low, high)) = self.bounds.get(*name) {
+                (lower[i], upper[i]) = (low, high);
+                values[i] = values[i].clamp(low, high);
+            }
+        }
+
+        let free: Vec<usize> = (0..PARAM_NAMES.len())
+            .filter(|&i| !self.frozen.iter().any(|name| name == PARAM_NAMES[i]))
+            .filter(|&i| !values[i].is_nan())
+            .collect();
+        if free.is_empty() {
+            return Err(Error::Fit(
+                "all current sheet parameters are frozen".to_string(),
+            ));
+        }
+
+        // Only the current sheet changes during the fit, so the fixed field is removed once.
+        let data = &observed - &self.fixed_field.parmap_calc_field(positions);
+
+        let mut current = model(&values, positions)?;
+        let mut residuals = &data - &current;
+        let mut cost = sum_squares(&residuals);
+        let mut jacobian = self.jacobian(&values, &current, &free, positions)?;
+        let mut damping = 1e-3;
+        let (mut iterations, mut converged) = (0, false);
+
+        while iterations < self.max_iterations && !converged {
+            iterations += 1;
+
+            let gradient = jacobian.t().dot(&flatten(&residuals));
+            let normal = jacobian.t().dot(&jacobian);
+            let mut accepted = None;
+
+            // Increase the damping until a step lowers the misfit. Steps are clamped to the
+            // bounds, and steps to invalid parameters are rejected like any other bad step.
+            while accepted.is_none() && damping < MAX_DAMPING {
+                let mut damped = normal.clone();
+                for k in 0..free.len() {
+                    damped[[k, k]] += damping * normal[[k, k]].max(f64::MIN_POSITIVE);
+                }
+
+                if let Some(l) = cholesky(&damped) {
+                    let step = cholesky_solve(&l, gradient.view());
+                    let mut trial = values;
+                    for (k, &i) in free.iter().enumerate() {
+                        trial[i] = (values[i] + step[k]).clamp(lower[i], upper[i]);
+                    }
+
+                    if let Ok(trial_model) = model(&trial, positions) {
+                        let trial_residuals = &data - &trial_model;
+                        let trial_cost = sum_squares(&trial_residuals);
+                        if trial_cost < cost {
+                            accepted = Some((trial, trial_model, trial_residuals, trial_cost));
+                            continue;
+                        }
+                    }
+                }
+                damping *= 10.;
+            }
+
+            // No step lowers the misfit any more, so this is the minimum to numerical precision.
+            let Some((trial, trial_model, trial_residuals, trial_cost)) = accepted else {
+                converged = true;
+                break;
+            };
+
+            converged = (cost - trial_cost) <= self.tolerance * cost;
+            (values, current, residuals, cost) = (trial, trial_model, trial_residuals, trial_cost);
+            jacobian = self.jacobian(&values, &current, &free, positions)?;
+            damping = (damping / 10.).max(1e-12);
+        }
+
+        let dof = residuals.len().saturating_sub(free.len()).max(1);
+        let normal = jacobian.t().dot(&jacobian);
+        let covariance = match cholesky(&normal) {
+            Some(l) => cholesky_inverse(&l) * (cost / dof as f64),
+            None => Array2::from_elem((free.len(), free.len()), f64::NAN),
+        };
+
+        Ok(CurrentSheetFitResult {
+            field: CurrentSheetField::from_params(
+                CurrentSheetParams::from_array(values)?,
+                IntegrationType::Analytic,
+            ),
+            param_names: free.iter().map(|&i| PARAM_NAMES[i]).collect(),
+            covariance,
+            rms: (cost / residuals.len() as f64).sqrt(),
+            residuals,
+            iterations,
+            converged,
+        })
+    }
+
+    /// Derivatives of the current sheet field at all positions with respect to the free
+    /// parameters, of shape (3N, number of free parameters), given the field `base` for
+    /// `values`. Falls back to a one-sided difference where a step would make the parameters
+    /// invalid, e.g. theta_d at 0.
+    fn jacobian(
+        &self,
+        values: &[f64; 7],
+        base: &Array2<f64>,
+        free: &[usize],
+        positions: ArrayView2<f64>,
+    ) -> Result<Array2<f64>, Error> {
+        let mut jacobian = Array2::<f64>::zeros((base.len(), free.len()));
+
+        for (k, &i) in free.iter().enumerate() {
+            let step = JACOBIAN_STEP * values[i].abs().max(1.);
+            let (mut plus, mut minus) = (*values, *values);
+            plus[i] += step;
+            minus[i] -= step;
+
+            let derivative = match (model(&plus, positions), model(&minus, positions)) {
+                (Ok(b_plus), Ok(b_minus)) => (b_plus - b_minus) / (2. * step),
+                (Ok(b_plus), Err(_)) => (b_plus - base) / step,
+                (Err(_), Ok(b_minus)) => (base - b_minus) / step,
+                (Err(e), Err(_)) => return Err(e),
+            };
+            jacobian.column_mut(k).assign(&flatten(&derivative));
+        }
+        Ok(jacobian)
+    }
+}
+
+/// The analytic current sheet field with parameters `values` at all positions.
+fn model(values: &[f64; 7], positions: ArrayView2<f64>) -> Result<Array2<f64>, Error> {
+    let field = CurrentSheetField::from_params(
+        CurrentSheetParams::from_array(*values)?,
+        IntegrationType::Analytic,
+    );
+    Ok(field.parmap_calc_field(positions))
+}
+
+fn flatten(x: &Array2<f64>) -> Array1<f64> {
+    x.iter().copied().collect()
+}
+
+fn sum_squares(x: &Array2<f64>) -> f64 {
+    x.iter().map(|v| v * v).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_currentsheet_fit() {
+        use crate::currentsheet::{
+            CurrentSheetField, CurrentSheetParams, IntegrationType, PARAM_NAMES,
+        };
+        use crate::error::Error;
+        use crate::field::Field;
+        use crate::fit::CurrentSheetFit;
+        use crate::internal::InternalField;
+        use ndarray::{Array2, s};
+
+        // Synthetic observations along a spread of radii, latitudes and longitudes near the
+        // sheet, from a perturbed CON2020.
+        let positions = Array2::from_shape_fn((300, 3), |(i, j)| match j {
+            0 => 6. + (i % 30) as f64,
+            1 => 1.1 + 0.003 * i as f64,
+            _ => 0.61 * i as f64,
+        });
+        let params_test = CurrentSheetParams::con2020()
+            .to_builder()
+            .mu0_i_2(150.)
+            .d(3.1)
+            .r_0(8.2)
+            .theta_d_degrees(9.8)
+            .phi_d_degrees(200.)
+            .i_rho(14.)
+            .build()
+            .unwrap();
+        let internal_field = InternalField::new("JRM09", None, None, None);
+        let observed = internal_field.map_calc_field(positions.view())
+            + CurrentSheetField::from_params(params_test, IntegrationType::Analytic)
+                .map_calc_field(positions.view());
+
+        let result = CurrentSheetFit::new(&internal_field, CurrentSheetParams::con2020())
+            .freeze("r_1")
+            .solve(positions.view(), observed.view())
+            .unwrap();
+
+        let (val, val_test) = (result.field.params().to_array(), params_test.to_array());
+        for i in 0..7 {
+            assert!(
+                (val[i] / val_test[i] - 1.).abs() < 1e-5,
+                "Current Sheet Fit Test Failed: \n Calculated {:?}, Expected {:?}",
+                val,
+                val_test
+            );
+        }
+        assert!(
+            result.converged
+                && result.param_names.len() == 6
+                && result.covariance.diag().iter().all(|x| *x >= 0.),
+            "Current Sheet Fit Test Failed: \n Calculated {:?}",
+            result.param_names
+        );
+
+        // Bounds are respected, and frozen parameters keep their initial value.
+        let result = CurrentSheetFit::new(&internal_field, CurrentSheetParams::con2020())
+            .freeze("r_1")
+            .freeze("d")
+            .bounds("mu0_i_2", 100., 145.)
+            .solve(positions.view(), observed.view())
+            .unwrap();
+        let params = result.field.params();
+        assert!(
+            params.mu0_i_2() <= 145. && params.d() == CurrentSheetParams::con2020().d(),
+            "Current Sheet Fit Bounds Test Failed: \n Calculated {:?}",
+            params
+        );
+
+        assert!(
+            CurrentSheetFit::new(&internal_field, CurrentSheetParams::con2020())
+                .freeze("r_2")
+                .solve(positions.view(), observed.view())
+                .is_err(),
+            "Current Sheet Fit Test Failed"
+        );
+
+        // Reversed or NaN bounds are rejected rather than panicking in the clamp.
+        for (lower, upper) in [(5., 2.), (f64::NAN, 5.), (2., f64::NAN)] {
+            let val = CurrentSheetFit::new(&internal_field, CurrentSheetParams::con2020())
+                .bounds("d", lower, upper)
+                .solve(positions.view(), observed.view());
+            assert!(
+                matches!(val, Err(Error::Fit(_))),
+                "Current Sheet Fit Bounds Test Failed for {:?}",
+                (lower, upper)
+            );
+        }
+
+        let val = PARAM_NAMES
+            .iter()
+            .fold(
+                CurrentSheetFit::new(&internal_field, CurrentSheetParams::con2020()),
+                |fit, name| fit.freeze(name),
+            )
+            .solve(positions.view(), observed.view());
+        assert!(
+            matches!(val, Err(Error::Fit(_))),
+            "Current Sheet Fit Test Failed"
+        );
+
+        for (positions, observed) in [
+            (positions.view(), observed.slice(s![.., ..2])),
+            (positions.slice(s![..0, ..]), observed.slice(s![..0, ..])),
+        ] {
+            let val = CurrentSheetFit::new(&internal_field, CurrentSheetParams::con2020())
+                .solve(positions, observed);
+            assert!(
+                matches!(val, Err(Error::InvalidShape(_))),
+                "Current Sheet Fit Test Failed"
+            );
+        }
+    }
+}
@@ -1,6 +1,10 @@
-use crate::field::Field;
-use crate::legendre;
-use ndarray::{ArcArray2, Array1, Array2, s};
+use crate::convert;
+use crate::error::Error;
+use crate::field::{Field, numerical_jacobian_xyz};
+use crate::legendre::{LEGENDRE_WORKSPACE, LegendreWorkspace};
+use crate::models::{self, ModelInfo};
+use crate::time::Time;
+use ndarray::{ArcArray2, Array1, Array2, Array3, ArrayView1, ArrayView2, ArrayViewMut2, Zip, s};
 
 /// Struct to characterize an internal field model using Schmidt coefficients.
 #[derive(Clone)]
@@ -9,76 +13,827 @@ pub struct InternalField {
     g: ArcArray2<f64>,
     h: ArcArray2<f64>,
     degree: usize,
+    info: Option<ModelInfo>,
+    sv: Option<Box<SecularVariation>>,
+    // Covariance of the coefficients in the order of `coefficient_index`, in nT^2.
+    covariance: Option<ArcArray2<f64>>,
+}
+
+/// Secular variation of an internal field model relative to the epoch of its main field.
+#[derive(Clone)]
+struct SecularVariation {
+    // The field is linear in the coefficients, so the rate of change of the field is itself an
+    // internal field built from the dg/dt and dh/dt coefficients (nT / year).
+    rate: InternalField,
+    epoch: f64,
 }
 
 impl InternalField {
-    /// Instantiate a new `InternalField` struct.
+    /// Instantiate a new `InternalField` struct. Panics if the inputs are invalid, see
+    /// [`InternalField::try_new`] for the fallible version.
     pub fn new(
         field_type: &str,
         g_in: Option<Array2<f64>>,
         h_in: Option<Array2<f64>>,
         degree_in: Option<usize>,
     ) -> Self {
-        let mut field = match field_type {
-            "JRM09" => create_jrm09_field(),
-            "JRM33" => create_jrm33_field(),
-            "Custom" => {
-                let expectmessage = "g and h are expected for Custom field type.";
-                InternalField {
-                    g: g_in.expect(expectmessage).to_shared(),
-                    h: h_in.expect(expectmessage).to_shared(),
-                    degree: 0,
-                }
+        Self::try_new(field_type, g_in, h_in, degree_in).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Instantiate a new `InternalField` struct, returning an error if `field_type` is unknown or
+    /// the custom coefficients are missing or inconsistent. `field_type` is either "Custom" or
+    /// the name of one of the bundled models in [`models::available_models`].
+    pub fn try_new(
+        field_type: &str,
+        g_in: Option<Array2<f64>>,
+        h_in: Option<Array2<f64>>,
+        degree_in: Option<usize>,
+    ) -> Result<Self, Error> {
+        let mut field = if field_type == "Custom" {
+            let (Some(g), Some(h)) = (g_in, h_in) else {
+                return Err(Error::MissingInput(
+                    "g and h are expected for Custom field type.",
+                ));
+            };
+            check_coefficient_shapes(&g, &h)?;
+
+            InternalField {
+                g: g.to_shared(),
+                h: h.to_shared(),
+                degree: 0,
+                info: None,
+                sv: None,
+                covariance: None,
             }
-            _ => panic!("Unknown field_type: Supported (JRM09, JRM33, Custom)"),
+        } else {
+            let Some(info) = models::model_info(field_type) else {
+                let mut supported: Vec<&str> = models::available_models()
+                    .iter()
+                    .map(|m| m.name.as_ref())
+                    .collect();
+                supported.push("Custom");
+                return Err(Error::UnknownModel {
+                    name: field_type.to_string(),
+                    supported: supported.join(", "),
+                });
+            };
+
+            let mut field = match info.name.as_ref() {
+                "JRM09" => create_jrm09_field(),
+                "JRM33" | "JRM33_13" => create_jrm33_field(),
+                "O6" => create_o6_field(),
+                "VIP4" => create_vip4_field(),
+                _ => return Err(Error::ModelNotBundled(info.name.to_string())),
+            };
+            field.info = Some(info.clone());
+            field
         };
 
-        if let Some(x) = degree_in
-            && x < field.g.nrows()
-        {
+        // Named models are also limited to their own maximum degree, e.g. for truncated variants.
+        let max_degree = field
+            .info
+            .as_ref()
+            .map_or(usize::MAX, |info| info.max_degree);
+        field = field.truncate(degree_in.unwrap_or(usize::MAX).min(max_degree));
+
+        field.degree = field.g.nrows() - 1;
+        Ok(field)
+    }
+
+    /// Truncate the coefficients to `degree`, if they extend past it.
+    fn truncate(mut self, degree: usize) -> Self {
+        if degree < self.g.nrows() - 1 {
             // Workaround to mutate ArcArray via copy.
             // We are only doing this when initializing, so this should
             // not impact performance as much.
 
-            field.g = field
+            self.g = self
                 .g
                 .into_owned()
-                .slice_move(s![..x + 1, ..x + 1])
+                .slice_move(s![..degree + 1, ..degree + 1])
                 .to_shared();
 
-            field.h = field
+            self.h = self
                 .h
                 .into_owned()
-                .slice_move(s![..x + 1, ..x + 1])
+                .slice_move(s![..degree + 1, ..degree + 1])
                 .to_shared();
         }
+        self
+    }
 
-        field.degree = field.g.nrows() - 1;
-        field = field.normalize_coefficients();
+    /// Return the registry metadata for this model, or `None` for custom coefficients.
+    pub fn model_info(&self) -> Option<&ModelInfo> {
+        self.info.as_ref()
+    }
+
+    /// Attach model metadata, e.g. to describe custom coefficients from a new inversion.
+    pub fn with_model_info(mut self, info: ModelInfo) -> Self {
+        self.info = Some(info);
+        self
+    }
+
+    /// Add secular variation to the model. `dg` and `dh` are the rates of change of the Schmidt
+    /// coefficients in nT / year, and `epoch` is the time at which the main field coefficients
+    /// apply. The secular variation may be of a lower degree than the main field.
+    pub fn with_secular_variation(
+        mut self,
+        dg: Array2<f64>,
+        dh: Array2<f64>,
+        epoch: Time,
+    ) -> Result<Self, Error> {
+        check_coefficient_shapes(&dg, &dh)?;
+        if dg.nrows() > self.degree + 1 {
+            return Err(Error::InvalidCoefficients(format!(
+                "secular variation degree {} exceeds the main field degree {}",
+                dg.nrows() - 1,
+                self.degree
+            )));
+        }
+
+        self.sv = Some(Box::new(SecularVariation {
+            rate: InternalField::try_new("Custom", Some(dg), Some(dh), None)?,
+            epoch: epoch.decimal_year(),
+        }));
+        Ok(self)
+    }
+
+    /// Epoch of the main field coefficients as a decimal year, if the model has secular
+    /// variation.
+    pub fn epoch(&self) -> Option<f64> {
+        self.sv.as_ref().map(|sv| sv.epoch)
+    }
+
+    /// Get the secular variation coefficients (dg/dt, dh/dt) in nT / year, if any.
+    pub fn get_secular_variation(&self) -> Option<(Array2<f64>, Array2<f64>)> {
+        self.sv.as_ref().map(|sv| sv.rate.get_coefficients())
+    }
+
+    /// Return the model evolved linearly to `time`. The returned model keeps its secular
+    /// variation, with its epoch moved to `time`. Models without secular variation are returned
+    /// unchanged.
+    pub fn at_time(&self, time: Time) -> InternalField {
+        let Some(sv) = &self.sv else {
+            return self.clone();
+        };
+
+        let dt = time.decimal_year() - sv.epoch;
+        let size = sv.rate.degree + 1;
+
+        let mut g = self.g.to_owned();
+        let mut h = self.h.to_owned();
+        g.slice_mut(s![..size, ..size]).scaled_add(dt, &sv.rate.g);
+        h.slice_mut(s![..size, ..size]).scaled_add(dt, &sv.rate.h);
+
+        let mut field = self.clone();
+        field.g = g.to_shared();
+        field.h = h.to_shared();
+        field.sv = Some(Box::new(SecularVariation {
+            rate: sv.rate.clone(),
+            epoch: time.decimal_year(),
+        }));
         field
     }
 
-    fn normalize_coefficients(mut self) -> Self {
-        // Normalize the coefficients here rather than in the calculation
-        // Since g and h are ArcArray, they are immutable, hence
-        // the into_owned and to_shared workaround. This is ugly but it is
-        // only done once when the field is initialized.
+    /// Calculate the field (Br, Btheta, Bphi) at `time`, evolving the model linearly from its
+    /// epoch. Without secular variation this is the same as [`Field::calc_field_array`].
+    pub fn calc_field_at_time(&self, r: f64, theta: f64, phi: f64, time: Time) -> [f64; 3] {
+        let b = self.calc_field_array(r, theta, phi);
+        let Some(sv) = &self.sv else {
+            return b;
+        };
 
-        let s = legendre::schmidt_semi_normalization_constants(&self.degree);
-        self.g = (self.g.into_owned() * &s).to_shared();
-        self.h = (self.h.into_owned() * &s).to_shared();
-        self
+        let dt = time.decimal_year() - sv.epoch;
+        let db = sv.rate.calc_field_array(r, theta, phi);
+        [b[0] + dt * db[0], b[1] + dt * db[1], b[2] + dt * db[2]]
+    }
+
+    /// Calculate the field at a collection of points (`positions`, shape (N, 3) of (r, theta,
+    /// phi)), each at its own time in `times` (shape (N,), decimal years). Returns
+    /// [`Error::InvalidShape`] if there is not one time per position.
+    pub fn map_calc_field_at_time(
+        &self,
+        positions: ArrayView2<f64>,
+        times: ArrayView1<f64>,
+    ) -> Result<Array2<f64>, Error> {
+        check_times(positions, times)?;
+        let mut result = Array2::<f64>::zeros((positions.nrows(), 3));
+
+        Zip::from(result.rows_mut())
+            .and(positions.rows())
+            .and(&times)
+            .for_each(|mut x, y, &t| {
+                x.assign(&ArrayView1::from(&self.calc_field_at_time(
+                    y[0],
+                    y[1],
+                    y[2],
+                    Time::DecimalYear(t),
+                )));
+            });
+
+        Ok(result)
+    }
+
+    /// Similar to [`InternalField::map_calc_field_at_time`], but uses Rayon for parallelizing.
+    pub fn parmap_calc_field_at_time(
+        &self,
+        positions: ArrayView2<f64>,
+        times: ArrayView1<f64>,
+    ) -> Result<Array2<f64>, Error> {
+        check_times(positions, times)?;
+        let mut result = Array2::<f64>::zeros((positions.nrows(), 3));
+
+        Zip::from(result.rows_mut())
+            .and(positions.rows())
+            .and(&times)
+            .par_for_each(|mut x, y, &t| {
+                x.assign(&ArrayView1::from(&self.calc_field_at_time(
+                    y[0],
+                    y[1],
+                    y[2],
+                    Time::DecimalYear(t),
+                )));
+            });
+
+        Ok(result)
+    }
+
+    /// Calculate the magnetic scalar potential V at spherical coordinates (r, theta, phi), such
+    /// that B = -grad V. With r in planetary radii, V is in nT planetary radii:
+    ///
+    /// V = a sum_n (a / r)^(n + 1) sum_m P_n^m(cos theta) (g_n^m cos(m phi) + h_n^m sin(m phi))
+    pub fn calc_potential(&self, r: f64, theta: f64, phi: f64) -> f64 {
+        LEGENDRE_WORKSPACE.with_borrow_mut(|workspace| {
+            workspace.compute(theta, self.degree);
+            self.sum_potential(workspace.p(), r, phi)
+        })
+    }
+
+    /// Calculate the scalar potential at a collection of points (`positions`) of shape (N, 3)
+    /// with (r, theta, phi) along the last axis. Returns an array of shape (N,).
+    pub fn map_calc_potential(&self, positions: ArrayView2<f64>) -> Array1<f64> {
+        let mut result = Array1::<f64>::zeros(positions.nrows());
+
+        Zip::from(&mut result)
+            .and(positions.rows())
+            .for_each(|x, y| {
+                *x = self.calc_potential(y[0], y[1], y[2]);
+            });
+
+        result
+    }
+
+    /// Similar to [`InternalField::map_calc_potential`], but uses Rayon for parallelizing.
+    pub fn parmap_calc_potential(&self, positions: ArrayView2<f64>) -> Array1<f64> {
+        let mut result = Array1::<f64>::zeros(positions.nrows());
+
+        Zip::from(&mut result)
+            .and(positions.rows())
+            .par_for_each(|x, y| {
+                *x = self.calc_potential(y[0], y[1], y[2]);
+            });
+
+        result
+    }
+
+    /// Attach the covariance matrix of the coefficients, in nT^2, e.g. from the inversion that
+    /// produced the model. Rows and columns follow [`coefficient_index`], so the matrix has shape
+    /// (degree (degree + 2), degree (degree + 2)). Returns an error if the shape is wrong or the
+    /// matrix is not symmetric with a non-negative diagonal.
+    pub fn with_covariance(mut self, covariance: Array2<f64>) -> Result<Self, Error> {
+        let size = self.degree * (self.degree + 2);
+        if covariance.dim() != (size, size) {
+            return Err(Error::InvalidCoefficients(format!(
+                "the covariance of a model of degree {} must have shape ({size}, {size}), got {:?}",
+                self.degree,
+                covariance.shape()
+            )));
+        }
+
+        let scale = covariance.diag().fold(0., |acc: f64, x| acc.max(x.abs()));
+        let asymmetric = Zip::from(&covariance)
+            .and(&covariance.t())
+            .any(|x, y| (x - y).abs() > 1e-9 * scale);
+        if asymmetric || covariance.diag().iter().any(|x| *x < 0.) {
+            return Err(Error::InvalidCoefficients(
+                "the covariance must be symmetric with a non-negative diagonal".to_string(),
+            ));
+        }
+
+        self.covariance = Some(covariance.to_shared());
+        Ok(self)
+    }
+
+    /// Attach uncorrelated 1-sigma uncertainties of the coefficients, in nT, with the same layout
+    /// as g and h. They may be of a lower degree than the model, in which case the higher degrees
+    /// are taken as exact. This is the diagonal case of [`InternalField::with_covariance`].
+    pub fn with_standard_deviations(
+        self,
+        sigma_g: Array2<f64>,
+        sigma_h: Array2<f64>,
+    ) -> Result<Self, Error> {
+        check_coefficient_shapes(&sigma_g, &sigma_h)?;
+        if sigma_g.nrows() > self.degree + 1 {
+            return Err(Error::InvalidCoefficients(format!(
+                "standard deviation degree {} exceeds the main field degree {}",
+                sigma_g.nrows() - 1,
+                self.degree
+            )));
+        }
+
+        let size = self.degree * (self.degree + 2);
+        let mut covariance = Array2::<f64>::zeros((size, size));
+        for n in 1..sigma_g.nrows() {
+            for m in 0..n + 1 {
+                let i = coefficient_index(n, m, false);
+                covariance[[i, i]] = sigma_g[[n, m]].powi(2);
+                if m > 0 {
+                    let i = coefficient_index(n, m, true);
+                    covariance[[i, i]] = sigma_h[[n, m]].powi(2);
+                }
+            }
+        }
+        self.with_covariance(covariance)
+    }
+
+    /// Get the covariance matrix of the coefficients in nT^2, if any. See
+    /// [`InternalField::with_covariance`] for the layout.
+    pub fn covariance(&self) -> Option<Array2<f64>> {
+        self.covariance.as_ref().map(|x| x.to_owned())
+    }
+
+    /// Derivatives of (Br, Btheta, Bphi) at (r, theta, phi) with respect to each coefficient, in
+    /// the order of [`coefficient_index`]. The field is linear in the coefficients, so these are
+    /// the rows of the design matrix, and the field is their product with the coefficients.
+    /// Returns an array of shape (3, degree (degree + 2)).
+    pub fn design_matrix_rows(&self, r: f64, theta: f64, phi: f64) -> Array2<f64> {
+        let mut rows = Array2::<f64>::zeros((3, self.degree * (self.degree + 2)));
+        LEGENDRE_WORKSPACE.with_borrow_mut(|workspace| {
+            workspace.compute(theta, self.degree);
+            fill_design_rows(workspace, self.degree, r, phi, rows.view_mut());
+        });
+        rows
+    }
+
+    /// Calculate the 1-sigma uncertainty of (Br, Btheta, Bphi) at (r, theta, phi) due to the
+    /// uncertainty of the coefficients, by linear propagation through
+    /// [`InternalField::design_matrix_rows`]. Returns an error if the model has no covariance.
+    pub fn calc_field_uncertainty(&self, r: f64, theta: f64, phi: f64) -> Result<[f64; 3], Error> {
+        let covariance = self.require_covariance()?;
+        Ok(self.field_uncertainty(covariance, r, theta, phi))
+    }
+
+    /// Calculate the field uncertainty at a collection of points (`positions`) of shape (N, 3)
+    /// with (r, theta, phi) along the last axis. Returns an array of shape (N, 3).
+    pub fn map_calc_field_uncertainty(
+        &self,
+        positions: ArrayView2<f64>,
+    ) -> Result<Array2<f64>, Error> {
+        let covariance = self.require_covariance()?;
+        let mut result = Array2::<f64>::zeros((positions.nrows(), 3));
+
+        Zip::from(result.rows_mut())
+            .and(positions.rows())
+            .for_each(|mut x, y| {
+                let val = self.field_uncertainty(covariance, y[0], y[1], y[2]);
+                x.assign(&ArrayView1::from(&val));
+            });
+
+        Ok(result)
+    }
+
+    /// Similar to [`InternalField::map_calc_field_uncertainty`], but uses Rayon for
+    /// parallelizing.
+    pub fn parmap_calc_field_uncertainty(
+        &self,
+        positions: ArrayView2<f64>,
+    ) -> Result<Array2<f64>, Error> {
+        let covariance = self.require_covariance()?;
+        let mut result = Array2::<f64>::zeros((positions.nrows(), 3));
+
+        Zip::from(result.rows_mut())
+            .and(positions.rows())
+            .par_for_each(|mut x, y| {
+                let val = self.field_uncertainty(covariance, y[0], y[1], y[2]);
+                x.assign(&ArrayView1::from(&val));
+            });
+
+        Ok(result)
+    }
+
+    /// The coefficient covariance, or an error if the model has none.
+    fn require_covariance(&self) -> Result<&ArcArray2<f64>, Error> {
+        self.covariance.as_ref().ok_or(Error::MissingInput(
+            "the model needs a coefficient covariance for field uncertainties.",
+        ))
+    }
+
+    /// The field uncertainty at (r, theta, phi) for the given coefficient covariance.
+    fn field_uncertainty(
+        &self,
+        covariance: &ArcArray2<f64>,
+        r: f64,
+        theta: f64,
+        phi: f64,
+    ) -> [f64; 3] {
+        let rows = self.design_matrix_rows(r, theta, phi);
+        let variance = (&rows.dot(covariance) * &rows).sum_axis(ndarray::Axis(1));
+        [variance[0].sqrt(), variance[1].sqrt(), variance[2].sqrt()]
     }
 
     /// Get the Schimdt coefficients for this internal field model.
     pub fn get_coefficients(&self) -> (Array2<f64>, Array2<f64>) {
-        let s = legendre::schmidt_semi_normalization_constants(&self.degree);
-        (self.g.to_owned() / &s, self.h.to_owned() / &s)
+        (self.g.to_owned(), self.h.to_owned())
+    }
+
+    /// Calculate the Lowes-Mauersberger spatial power spectrum at radius `r` (in planetary
+    /// radii), in nT^2. Entry n of the returned array is
+    ///
+    /// R_n(r) = (n + 1) (1 / r)^(2n + 4) sum_m (g_n^m^2 + h_n^m^2),
+    ///
+    /// the mean square field over the sphere of radius r due to degree n. Since each degree
+    /// scales as a power of r, the radius at which the spectrum flattens is an estimate of the
+    /// depth of the dynamo region.
+    pub fn power_spectrum(&self, r: f64) -> Array1<f64> {
+        let (g, h) = self.get_coefficients();
+        let a = 1. / r;
+
+        Array1::from_shape_fn(self.degree + 1, |n| {
+            let sum: f64 = (0..n + 1)
+                .map(|m| g[[n, m]].powi(2) + h[[n, m]].powi(2))
+                .sum();
+            (n + 1) as f64 * a.powi(2 * n as i32 + 4) * sum
+        })
+    }
+
+    /// Return the part of the model due to degrees `min_degree..=max_degree` only, e.g. (1, 1)
+    /// for the dipole. Secular variation is restricted to the same degrees. The returned field
+    /// has no model metadata, since it is no longer the published model.
+    pub fn degree_range(&self, min_degree: usize, max_degree: usize) -> Result<Self, Error> {
+        if min_degree > max_degree || max_degree > self.degree {
+            return Err(Error::InvalidCoefficients(format!(
+                "invalid degree range {min_degree}..={max_degree} for a model of degree {}",
+                self.degree
+            )));
+        }
+        Ok(self.select_degrees(min_degree, max_degree))
+    }
+
+    /// Split the model into one field per degree, where entry n contains only degree n. The
+    /// sum of the fields of all entries is the field of the full model.
+    pub fn split_degrees(&self) -> Vec<Self> {
+        (0..self.degree + 1)
+            .map(|n| self.select_degrees(n, n))
+            .collect()
+    }
+
+    /// Keep the coefficients of degrees `min_degree..=max_degree` and zero the rest.
+    /// Degrees above the degree of the model are ignored.
+    fn select_degrees(&self, min_degree: usize, max_degree: usize) -> Self {
+        let size = max_degree.min(self.degree) + 1;
+
+        let mut g = self.g.slice(s![..size, ..size]).to_owned();
+        let mut h = self.h.slice(s![..size, ..size]).to_owned();
+        g.slice_mut(s![..min_degree.min(size), ..]).fill(0.);
+        h.slice_mut(s![..min_degree.min(size), ..]).fill(0.);
+
+        let sv = self.sv.as_ref().map(|sv| {
+            Box::new(SecularVariation {
+                rate: sv.rate.select_degrees(min_degree, max_degree),
+                epoch: sv.epoch,
+            })
+        });
+
+        // Dropped coefficients become exact, and the covariance of the kept degrees is unchanged.
+        let covariance = self.covariance.as_ref().map(|covariance| {
+            let (start, end) = (
+                min_degree.min(size).max(1).pow(2) - 1,
+                (size - 1) * (size + 1),
+            );
+            let mut covariance = covariance.slice(s![..end, ..end]).to_owned();
+            covariance.slice_mut(s![..start, ..]).fill(0.);
+            covariance.slice_mut(s![.., ..start]).fill(0.);
+            covariance.to_shared()
+        });
+
+        InternalField {
+            g: g.to_shared(),
+            h: h.to_shared(),
+            degree: size - 1,
+            info: None,
+            sv,
+            covariance,
+        }
+    }
+
+    /// Express the model in a rotated frame. `rotation` is the (3, 3) matrix that rotates
+    /// cartesian vectors from the frame of the model into the new frame, x_new = R x, for
+    /// example from [`convert::rot_matrix_iau_to_mag`]. The returned model satisfies
+    /// B_new(R x) = R B(x), and has no model metadata.
+    ///
+    /// Each degree is rotated with the real Wigner D matrix of that degree, computed with the
+    /// recursion of Ivanic and Ruedenberg (1996), J. Phys. Chem. 100, 6342-6347.
+    pub fn rotate(&self, rotation: ArrayView2<f64>) -> Result<Self, Error> {
+        let rotation = check_rotation(rotation)?;
+        let wigner_d = real_wigner_d(&rotation, self.degree);
+
+        let (g, h) = self.get_coefficients();
+        let (g, h) = rotate_coefficients(&g, &h, &wigner_d);
+        let mut field = InternalField::try_new("Custom", Some(g), Some(h), None)?;
+
+        if let Some(sv) = &self.sv {
+            let (dg, dh) = sv.rate.get_coefficients();
+            let (dg, dh) = rotate_coefficients(&dg, &dh, &wigner_d);
+            field = field.with_secular_variation(dg, dh, Time::DecimalYear(sv.epoch))?;
+        }
+
+        // The coefficients transform linearly, c_new = T c, so the covariance becomes T C T^T.
+        if let Some(covariance) = &self.covariance {
+            let transform = coefficient_rotation(&wigner_d);
+            field.covariance = Some(transform.dot(covariance).dot(&transform.t()).to_shared());
+        }
+        Ok(field)
+    }
+
+    /// Express the model in the MAG frame of its own dipole, see [`InternalField::dipole`] and
+    /// [`convert::vec_iau_to_mag`]. In this frame the dipole is aligned with the z axis, so g11
+    /// and h11 vanish.
+    pub fn in_dipole_frame(&self) -> Result<Self, Error> {
+        let dipole = self.dipole()?;
+        let rotation = convert::rot_matrix_iau_to_mag(dipole.tilt, dipole.pole_longitude);
+        self.rotate(rotation.view())
+    }
+
+    /// Calculate the field (Br, Btheta, Bphi) on a regular grid of colatitudes `thetas` and
+    /// longitudes `phis` at a fixed radius `r`. Returns an array of shape (Ntheta, Nphi, 3).
+    ///
+    /// This is much faster than evaluating each point separately, since the Legendre
+    /// polynomials are only computed once per theta row and the cos(m phi) / sin(m phi) terms
+    /// once per phi column. Rows are computed in parallel using Rayon.
+    pub fn calc_field_grid(
+        &self,
+        r: f64,
+        thetas: ArrayView1<f64>,
+        phis: ArrayView1<f64>,
+    ) -> Array3<f64> {
+        let radii = Array1::from_elem(thetas.len(), r);
+        self.grid_field(radii.view(), thetas, phis)
+    }
+
+    /// Same as [`InternalField::calc_field_grid`], but with a different radius for each theta
+    /// row. This is useful for axisymmetric surfaces such as the oblate 1 bar surface.
+    ///
+    /// Returns [`Error::InvalidShape`] if `radii` and `thetas` have different lengths.
+    pub fn calc_field_grid_radii(
+        &self,
+        radii: ArrayView1<f64>,
+        thetas: ArrayView1<f64>,
+        phis: ArrayView1<f64>,
+    ) -> Result<Array3<f64>, Error> {
+        if radii.len() != thetas.len() {
+            return Err(Error::InvalidShape(format!(
+                "radii and thetas must have the same length, got {} and {}",
+                radii.len(),
+                thetas.len()
+            )));
+        }
+        Ok(self.grid_field(radii, thetas, phis))
+    }
+
+    /// The grid evaluation behind [`InternalField::calc_field_grid_radii`], for radii and thetas
+    /// of the same length.
+    fn grid_field(
+        &self,
+        radii: ArrayView1<f64>,
+        thetas: ArrayView1<f64>,
+        phis: ArrayView1<f64>,
+    ) -> Array3<f64> {
+        let size = self.degree + 1;
+
+        // cos(m phi) and sin(m phi) for every column, shape (Nphi, degree + 1).
+        let mut cos_mphi = Array2::<f64>::zeros((phis.len(), size));
+        let mut sin_mphi = Array2::<f64>::zeros((phis.len(), size));
+        for (k, &phi) in phis.iter().enumerate() {
+            for m in 0..size {
+                let (sin, cos) = (m as f64 * phi).sin_cos();
+                cos_mphi[[k, m]] = cos;
+                sin_mphi[[k, m]] = sin;
+            }
+        }
+
+        let mut result = Array3::<f64>::zeros((thetas.len(), phis.len(), 3));
+
+        Zip::from(result.outer_iter_mut())
+            .and(&radii)
+            .and(&thetas)
+            .par_for_each(|mut row, &r, &theta| {
+                let coeffs = LEGENDRE_WORKSPACE.with_borrow_mut(|workspace| {
+                    workspace.compute(theta, self.degree);
+                    self.order_coefficients(workspace, r)
+                });
+
+                for (k, mut b) in row.outer_iter_mut().enumerate() {
+                    let (cos_k, sin_k) = (cos_mphi.row(k), sin_mphi.row(k));
+
+                    for m in 0..size {
+                        let [r_g, r_h, t_g, t_h, p_g, p_h] = coeffs[m];
+                        b[0] += r_g * cos_k[m] + r_h * sin_k[m];
+                        b[1] += t_g * cos_k[m] + t_h * sin_k[m];
+                        b[2] += p_g * sin_k[m] - p_h * cos_k[m];
+                    }
+                }
+            });
+
+        result
+    }
+
+    /// Sum the field over degree for each order m at a fixed (r, theta), leaving only the
+    /// longitude dependence. Each entry holds the cos(m phi) and sin(m phi) coefficients of
+    /// Br and Btheta, followed by the sin(m phi) and -cos(m phi) coefficients of Bphi.
+    fn order_coefficients(&self, workspace: &LegendreWorkspace, r: f64) -> Vec<[f64; 6]> {
+        let (p, dp, p_over_sin) = (workspace.p(), workspace.dp(), workspace.p_over_sin());
+        let a: f64 = 1. / r;
+
+        let mut coeffs = vec![[0.; 6]; self.degree + 1];
+        let mut a_pow = a;
+
+        for i in 0..self.degree + 1 {
+            a_pow *= a;
+
+            let (g_i, h_i) = (self.g.row(i), self.h.row(i));
+            let (p_i, dp_i, p_over_sin_i) = (p.row(i), dp.row(i), p_over_sin.row(i));
+
+            for j in 0..i + 1 {
+                let radial = a_pow * (i + 1) as f64 * p_i[j];
+                let polar = -a_pow * dp_i[j];
+
+                coeffs[j][0] += radial * g_i[j];
+                coeffs[j][1] += radial * h_i[j];
+                coeffs[j][2] += polar * g_i[j];
+                coeffs[j][3] += polar * h_i[j];
+
+                if j > 0 {
+                    let azimuthal = a_pow * p_over_sin_i[j] * j as f64;
+                    coeffs[j][4] += azimuthal * g_i[j];
+                    coeffs[j][5] += azimuthal * h_i[j];
+                }
+            }
+        }
+
+        coeffs
     }
 }
 
 impl Field for InternalField {
-    fn calc_field(&self, r: f64, theta: f64, phi: f64) -> Array1<f64> {
+    fn calc_field_array(&self, r: f64, theta: f64, phi: f64) -> [f64; 3] {
+        LEGENDRE_WORKSPACE.with_borrow_mut(|workspace| {
+            workspace.compute(theta, self.degree);
+            self.sum_field(workspace, r, phi)
+        })
+    }
+
+    /// Analytic gradient tensor, using second derivatives of the Legendre polynomials. Falls
+    /// back to [`numerical_jacobian_xyz`] on the polar axis, where the spherical basis is
+    /// singular.
+    fn calc_jacobian_xyz_array(&self, x: f64, y: f64, z: f64) -> [[f64; 3]; 3] {
+        let [r, theta, phi] = convert::pos_xyz_to_rtp_array([x, y, z]);
+
+        let jacobian_rtp = LEGENDRE_WORKSPACE.with_borrow_mut(|workspace| {
+            workspace.compute_second_derivative(theta, self.degree);
+            self.sum_jacobian(workspace, r, theta, phi)
+        });
+
+        if jacobian_rtp.iter().flatten().any(|x| !x.is_finite()) {
+            return numerical_jacobian_xyz(self, x, y, z);
+        }
+
+        // Rotate from the spherical to the cartesian basis, J_xyz = R J_rtp R^T, where the
+        // columns of R are the spherical unit vectors.
+        let basis = [
+            convert::vec_rtp_to_xyz_array([1., 0., 0.], theta, phi),
+            convert::vec_rtp_to_xyz_array([0., 1., 0.], theta, phi),
+            convert::vec_rtp_to_xyz_array([0., 0., 1.], theta, phi),
+        ];
+
+        let mut jacobian = [[0.; 3]; 3];
+        for (i, row) in jacobian.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                for (a, basis_a) in basis.iter().enumerate() {
+                    for (b, basis_b) in basis.iter().enumerate() {
+                        *value += basis_a[i] * jacobian_rtp[a][b] * basis_b[j];
+                    }
+                }
+            }
+        }
+        jacobian
+    }
+}
+
+impl InternalField {
+    /// Gradient tensor in the local spherical basis, J[i][j] = e_i . (e_j . grad) B.
+    fn sum_jacobian(
+        &self,
+        workspace: &LegendreWorkspace,
+        r: f64,
+        theta: f64,
+        phi: f64,
+    ) -> [[f64; 3]; 3] {
+        let (p, dp, d2p) = (workspace.p(), workspace.dp(), workspace.d2p());
+        let (g, h) = (&self.g, &self.h);
+
+        let a: f64 = 1. / r;
+        let (sintheta, costheta) = theta.sin_cos();
+        let inv_sintheta = 1. / sintheta;
+
+        // Field components and their partial derivatives with respect to (r, theta, phi).
+        let mut b = [0.; 3];
+        let mut db = [[0.; 3]; 3];
+
+        let mut a_pow = a;
+
+        for i in 0..self.degree + 1 {
+            a_pow *= a;
+
+            let n = i as f64;
+            let (g_i, h_i) = (g.row(i), h.row(i));
+            let (p_i, dp_i, d2p_i) = (p.row(i), dp.row(i), d2p.row(i));
+
+            for j in 0..i + 1 {
+                let m = j as f64;
+                let (sin_mphi, cos_mphi) = (m * phi).sin_cos();
+                let gh_cos = g_i[j] * cos_mphi + h_i[j] * sin_mphi;
+                let gh_sin = g_i[j] * sin_mphi - h_i[j] * cos_mphi;
+
+                b[0] += a_pow * (n + 1.) * p_i[j] * gh_cos;
+                db[0][0] -= a_pow * a * (n + 2.) * (n + 1.) * p_i[j] * gh_cos;
+                db[0][1] += a_pow * (n + 1.) * dp_i[j] * gh_cos;
+                db[0][2] -= a_pow * (n + 1.) * p_i[j] * m * gh_sin;
+
+                b[1] -= a_pow * dp_i[j] * gh_cos;
+                db[1][0] += a_pow * a * (n + 2.) * dp_i[j] * gh_cos;
+                db[1][1] -= a_pow * d2p_i[j] * gh_cos;
+                db[1][2] += a_pow * dp_i[j] * m * gh_sin;
+
+                if j > 0 {
+                    b[2] += a_pow * m * p_i[j] * gh_sin * inv_sintheta;
+                    db[2][0] -= a_pow * a * (n + 2.) * m * p_i[j] * gh_sin * inv_sintheta;
+                    db[2][1] += a_pow
+                        * m
+                        * gh_sin
+                        * (dp_i[j] * sintheta - p_i[j] * costheta)
+                        * inv_sintheta.powi(2);
+                    db[2][2] += a_pow * m * m * p_i[j] * gh_cos * inv_sintheta;
+                }
+            }
+        }
+
+        let cottheta = costheta * inv_sintheta;
+        let [b_r, b_theta, b_phi] = b;
+
+        [
+            [
+                db[0][0],
+                a * (db[0][1] - b_theta),
+                a * (db[0][2] * inv_sintheta - b_phi),
+            ],
+            [
+                db[1][0],
+                a * (db[1][1] + b_r),
+                a * (db[1][2] * inv_sintheta - cottheta * b_phi),
+            ],
+            [
+                db[2][0],
+                a * db[2][1],
+                a * (db[2][2] * inv_sintheta + b_r + cottheta * b_theta),
+            ],
+        ]
+    }
+
+    fn sum_potential(&self, p: &Array2<f64>, r: f64, phi: f64) -> f64 {
+        let a: f64 = 1. / r;
+
+        let mut v: f64 = 0.;
+
+        // (1 / r)^(n + 1), one power lower than for the field.
+        let mut a_pow = 1.;
+
+        for i in 0..self.degree + 1 {
+            a_pow *= a;
+
+            let (g_i, h_i, p_i) = (self.g.row(i), self.h.row(i), p.row(i));
+
+            for j in 0..i + 1 {
+                let (sin_mphi, cos_mphi) = (j as f64 * phi).sin_cos();
+                v += a_pow * p_i[j] * (g_i[j] * cos_mphi + h_i[j] * sin_mphi);
+            }
+        }
+
+        v
+    }
+
+    /// Sum the field (Br, Btheta, Bphi) over all degrees and orders. Bphi uses P / sin(theta),
+    /// so that it has the correct limit on the poles instead of dividing by zero.
+    fn sum_field(&self, workspace: &LegendreWorkspace, r: f64, phi: f64) -> [f64; 3] {
         let mut b_r: f64 = 0.;
         let mut b_theta: f64 = 0.;
         let mut b_phi: f64 = 0.;
@@ -87,63 +842,293 @@ impl Field for InternalField {
 
         let g = &self.g;
         let h = &self.h;
-        let degree = &self.degree;
-
-        assert!((g.nrows() == g.ncols()) && (h.nrows() == h.ncols()));
+        let degree = self.degree;
+        let (p, dp, p_over_sin) = (workspace.p(), workspace.dp(), workspace.p_over_sin());
 
-        let (p, dp) = legendre::assoc_legendre_poly(&theta, degree);
-
-        let sintheta: f64 = theta.sin();
-        let sinphi: f64 = phi.sin();
-        let cosphi: f64 = phi.cos();
-
-        let inv_sintheta: f64 = 1. / sintheta; // nan if zero, thats fine.
+        let (sinphi, cosphi) = phi.sin_cos();
 
         let mut sinphi_prev: f64;
         let mut cosphi_prev: f64;
         let mut sin_mphi: f64;
         let mut cos_mphi: f64;
 
+        // (1 / r)^(n + 2), updated incrementally rather than calling powi for every term.
+        let mut a_pow = a;
+
         for i in 0..degree + 1 {
             sinphi_prev = 0.;
             cosphi_prev = 1.;
 
-            let i_i32 = i32::try_from(i).unwrap();
+            a_pow *= a;
+
+            let (g_i, h_i) = (g.row(i), h.row(i));
+            let (p_i, dp_i, p_over_sin_i) = (p.row(i), dp.row(i), p_over_sin.row(i));
 
-            b_r += a.powi(i_i32 + 2) * (i + 1) as f64 * p[[i, 0]] * g[[i, 0]];
-            b_theta -= a.powi(i_i32 + 2) * dp[[i, 0]] * g[[i, 0]];
+            b_r += a_pow * (i + 1) as f64 * p_i[0] * g_i[0];
+            b_theta -= a_pow * dp_i[0] * g_i[0];
 
             for j in 1..i + 1 {
                 sin_mphi = sinphi_prev * cosphi + cosphi_prev * sinphi;
                 cos_mphi = cosphi_prev * cosphi - sinphi_prev * sinphi;
 
-                b_r += a.powi(i_i32 + 2)
-                    * (i + 1) as f64
-                    * p[[i, j]]
-                    * (g[[i, j]] * cos_mphi + h[[i, j]] * sin_mphi);
+                let gh_cos = g_i[j] * cos_mphi + h_i[j] * sin_mphi;
 
-                b_theta -=
-                    a.powi(i_i32 + 2) * dp[[i, j]] * (g[[i, j]] * cos_mphi + h[[i, j]] * sin_mphi);
+                b_r += a_pow * (i + 1) as f64 * p_i[j] * gh_cos;
 
-                b_phi += inv_sintheta
-                    * a.powi(i_i32 + 2)
-                    * p[[i, j]]
-                    * j as f64
-                    * (g[[i, j]] * sin_mphi - h[[i, j]] * cos_mphi);
+                b_theta -= a_pow * dp_i[j] * gh_cos;
+
+                b_phi +=
+                    a_pow * p_over_sin_i[j] * j as f64 * (g_i[j] * sin_mphi - h_i[j] * cos_mphi);
 
                 sinphi_prev = sin_mphi;
                 cosphi_prev = cos_mphi;
             }
         }
 
-        // Should set Bphi to zero if NaN.
-        if b_phi.is_nan() {
-            b_phi = 0.;
+        [b_r, b_theta, b_phi]
+    }
+}
+
+/// Check that g and h are square, non-empty and of the same shape.
+/// Check that there is one time per position for the `*_calc_field_at_time` maps.
+fn check_times(positions: ArrayView2<f64>, times: ArrayView1<f64>) -> Result<(), Error> {
+    if times.len() != positions.nrows() {
+        return Err(Error::InvalidShape(format!(
+            "times must have one entry per position, got {} times for {} positions",
+            times.len(),
+            positions.nrows()
+        )));
+    }
+    Ok(())
+}
+
+pub(crate) fn check_coefficient_shapes(g: &Array2<f64>, h: &Array2<f64>) -> Result<(), Error> {
+    if g.is_empty() || g.nrows() != g.ncols() {
+        return Err(Error::InvalidCoefficients(format!(
+            "g must be a non-empty square array, got shape {:?}",
+            g.shape()
+        )));
+    }
+    if h.shape() != g.shape() {
+        return Err(Error::InvalidCoefficients(format!(
+            "h must have the same shape as g {:?}, got {:?}",
+            g.shape(),
+            h.shape()
+        )));
+    }
+    Ok(())
+}
+
+/// Position of a coefficient in the flat ordering used for the covariance and the design
+/// matrix: g10, g11, h11, g20, g21, h21, g22, h22, ... The degree `n` must be at least 1, and
+/// `sine` selects h_n^m rather than g_n^m, for which `m` must be at least 1.
+pub fn coefficient_index(n: usize, m: usize, sine: bool) -> usize {
+    if m == 0 {
+        n * n - 1
+    } else {
+        n * n + 2 * m - 2 + sine as usize
+    }
+}
+
+/// Fill `rows` (shape (3, degree (degree + 2))) with the derivatives of (Br, Btheta, Bphi) with
+/// respect to each coefficient, from the Legendre polynomials in `workspace`.
+pub(crate) fn fill_design_rows(
+    workspace: &LegendreWorkspace,
+    degree: usize,
+    r: f64,
+    phi: f64,
+    mut rows: ArrayViewMut2<f64>,
+) {
+    let (p, dp, p_over_sin) = (workspace.p(), workspace.dp(), workspace.p_over_sin());
+    let a: f64 = 1. / r;
+    let mut a_pow = a * a;
+
+    for n in 1..degree + 1 {
+        a_pow *= a;
+
+        for m in 0..n + 1 {
+            let (sin_mphi, cos_mphi) = (m as f64 * phi).sin_cos();
+            let (b_r, b_theta, b_phi) = (
+                a_pow * (n + 1) as f64 * p[[n, m]],
+                -a_pow * dp[[n, m]],
+                a_pow * m as f64 * p_over_sin[[n, m]],
+            );
+
+            let i = coefficient_index(n, m, false);
+            rows[[0, i]] = b_r * cos_mphi;
+            rows[[1, i]] = b_theta * cos_mphi;
+            rows[[2, i]] = b_phi * sin_mphi;
+
+            if m > 0 {
+                let i = coefficient_index(n, m, true);
+                rows[[0, i]] = b_r * sin_mphi;
+                rows[[1, i]] = b_theta * sin_mphi;
+                rows[[2, i]] = -b_phi * cos_mphi;
+            }
+        }
+    }
+}
+
+/// Check that `rotation` is a proper (3, 3) rotation matrix.
+fn check_rotation(rotation: ArrayView2<f64>) -> Result<[[f64; 3]; 3], Error> {
+    if rotation.dim() != (3, 3) {
+        return Err(Error::InvalidRotation(format!(
+            "expected a (3, 3) matrix, got shape {:?}",
+            rotation.shape()
+        )));
+    }
+
+    let identity = rotation.dot(&rotation.t()) - Array2::<f64>::eye(3);
+    let det = rotation[[0, 0]]
+        * (rotation[[1, 1]] * rotation[[2, 2]] - rotation[[1, 2]] * rotation[[2, 1]])
+        - rotation[[0, 1]]
+            * (rotation[[1, 0]] * rotation[[2, 2]] - rotation[[1, 2]] * rotation[[2, 0]])
+        + rotation[[0, 2]]
+            * (rotation[[1, 0]] * rotation[[2, 1]] - rotation[[1, 1]] * rotation[[2, 0]]);
+
+    if identity.iter().any(|x| x.abs() > 1e-9) || (det - 1.).abs() > 1e-9 {
+        return Err(Error::InvalidRotation(
+            "the matrix must be orthogonal with determinant 1".to_string(),
+        ));
+    }
+
+    Ok([
+        [rotation[[0, 0]], rotation[[0, 1]], rotation[[0, 2]]],
+        [rotation[[1, 0]], rotation[[1, 1]], rotation[[1, 2]]],
+        [rotation[[2, 0]], rotation[[2, 1]], rotation[[2, 2]]],
+    ])
+}
+
+/// Real Wigner D matrices for degrees 0..=`degree`. Entry n has shape (2n + 1, 2n + 1), with
+/// index n + m for order m, where negative orders are the sin(|m| phi) harmonics. They satisfy
+/// Y_n^m(R x) = sum_k D_n[m, k] Y_n^k(x) for the Schmidt semi-normalized real harmonics.
+fn real_wigner_d(rotation: &[[f64; 3]; 3], degree: usize) -> Vec<Array2<f64>> {
+    let mut wigner_d = vec![Array2::<f64>::ones((1, 1))];
+    if degree == 0 {
+        return wigner_d;
+    }
+
+    // The degree 1 harmonics are proportional to (y, z, x) for m = -1, 0, 1.
+    let axis = [1, 2, 0];
+    wigner_d.push(Array2::from_shape_fn((3, 3), |(i, j)| {
+        rotation[axis[i]][axis[j]]
+    }));
+
+    for n in 2..degree + 1 {
+        let l = n as i64;
+        let r1 = &wigner_d[1];
+        let prev = &wigner_d[n - 1];
+
+        // Element (m, k) of the degree 1 and degree n - 1 matrices, indexed by order.
+        let r1_at = |i: i64, j: i64| r1[[(i + 1) as usize, (j + 1) as usize]];
+        let prev_at = |i: i64, j: i64| prev[[(i + l - 1) as usize, (j + l - 1) as usize]];
+
+        let p = |i: i64, a: i64, b: i64| {
+            if b == l {
+                r1_at(i, 1) * prev_at(a, l - 1) - r1_at(i, -1) * prev_at(a, 1 - l)
+            } else if b == -l {
+                r1_at(i, 1) * prev_at(a, 1 - l) + r1_at(i, -1) * prev_at(a, l - 1)
+            } else {
+                r1_at(i, 0) * prev_at(a, b)
+            }
+        };
+
+        let mut d_n = Array2::<f64>::zeros((2 * n + 1, 2 * n + 1));
+        for m in -l..l + 1 {
+            for k in -l..l + 1 {
+                let is_zero = if m == 0 { 1. } else { 0. };
+                let denominator = if k.abs() < l {
+                    ((l + k) * (l - k)) as f64
+                } else {
+                    (2 * l * (2 * l - 1)) as f64
+                };
+
+                let u = (((l + m) * (l - m)) as f64 / denominator).sqrt();
+                let v = 0.5
+                    * ((1. + is_zero) * ((l + m.abs() - 1) * (l + m.abs())) as f64 / denominator)
+                        .sqrt()
+                    * (1. - 2. * is_zero);
+                let w = -0.5
+                    * (((l - m.abs() - 1) * (l - m.abs())) as f64 / denominator).sqrt()
+                    * (1. - is_zero);
+
+                let mut value = 0.;
+                if u != 0. {
+                    value += u * p(0, m, k);
+                }
+                if v != 0. {
+                    value += v * match m {
+                        0 => p(1, 1, k) + p(-1, -1, k),
+                        1 => p(1, 0, k) * 2_f64.sqrt(),
+                        -1 => p(-1, 0, k) * 2_f64.sqrt(),
+                        m if m > 0 => p(1, m - 1, k) - p(-1, 1 - m, k),
+                        m => p(1, m + 1, k) + p(-1, -m - 1, k),
+                    };
+                }
+                if w != 0. {
+                    value += w * if m > 0 {
+                        p(1, m + 1, k) + p(-1, -m - 1, k)
+                    } else {
+                        p(1, m - 1, k) - p(-1, 1 - m, k)
+                    };
+                }
+                d_n[[(m + l) as usize, (k + l) as usize]] = value;
+            }
+        }
+        wigner_d.push(d_n);
+    }
+
+    wigner_d
+}
+
+/// Apply the real Wigner D matrices to each degree of the Schmidt coefficients.
+fn rotate_coefficients(
+    g: &Array2<f64>,
+    h: &Array2<f64>,
+    wigner_d: &[Array2<f64>],
+) -> (Array2<f64>, Array2<f64>) {
+    let mut g_rot = Array2::<f64>::zeros(g.dim());
+    let mut h_rot = Array2::<f64>::zeros(h.dim());
+
+    for n in 0..g.nrows() {
+        // Coefficients of degree n ordered by m = -n..=n, with h for negative orders.
+        let coeffs =
+            Array1::from_shape_fn(
+                2 * n + 1,
+                |i| {
+                    if i < n { h[[n, n - i]] } else { g[[n, i - n]] }
+                },
+            );
+        let rotated = wigner_d[n].dot(&coeffs);
+
+        for m in 0..n + 1 {
+            g_rot[[n, m]] = rotated[n + m];
+            if m > 0 {
+                h_rot[[n, m]] = rotated[n - m];
+            }
         }
+    }
+    (g_rot, h_rot)
+}
 
-        // return array of bx, by, bz
-        Array1::from_vec(vec![b_r, b_theta, b_phi])
+/// The block diagonal matrix of the real Wigner D matrices that rotates the coefficients in the
+/// order of [`coefficient_index`].
+fn coefficient_rotation(wigner_d: &[Array2<f64>]) -> Array2<f64> {
+    let degree = wigner_d.len() - 1;
+    let mut transform = Array2::<f64>::zeros((degree * (degree + 2), degree * (degree + 2)));
+    // Index of order m = -n..=n of degree n, with h for negative orders.
+    let index = |n: usize, m: isize| coefficient_index(n, m.unsigned_abs(), m < 0);
+
+    for (n, d) in wigner_d.iter().enumerate().skip(1) {
+        let n_signed = n as isize;
+        for k in -n_signed..n_signed + 1 {
+            for l in -n_signed..n_signed + 1 {
+                transform[[index(n, k), index(n, l)]] =
+                    d[[(n_signed + k) as usize, (n_signed + l) as usize]];
+            }
+        }
     }
+    transform
 }
 
 // Separating the JRM09 constants into a separate function
@@ -154,6 +1139,12 @@ fn create_jrm09_field() -> InternalField {
 
         degree: 10,
 
+        info: None,
+
+        sv: None,
+
+        covariance: None,
+
         g: ArcArray2::<f64>::from_shape_vec((11, 11), vec![
             0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
             410244.7, -71498.3, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
@@ -191,6 +1182,12 @@ fn create_jrm33_field() -> InternalField {
 
         degree: 30,
 
+        info: None,
+
+        sv: None,
+
+        covariance: None,
+
         g: ArcArray2::<f64>::from_shape_vec((31, 31), vec![
             0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
             410993.4, -71305.9, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
@@ -261,6 +1258,66 @@ fn create_jrm33_field() -> InternalField {
     }
 }
 
+#[rustfmt::skip]
+fn create_o6_field() -> InternalField {
+
+    InternalField {
+
+        degree: 3,
+
+        info: None,
+
+        sv: None,
+
+        covariance: None,
+
+        g: ArcArray2::<f64>::from_shape_vec((4, 4), vec![
+            0.0, 0.0, 0.0, 0.0,
+            424202.0, -65929.0, 0.0, 0.0,
+            -2181.0, -71106.0, 48714.0, 0.0,
+            7565.0, -15493.0, 19775.0, -17958.0,
+            ]).unwrap(),
+
+        h: ArcArray2::<f64>::from_shape_vec((4, 4), vec![
+            0.0, 0.0, 0.0, 0.0,
+            0.0, 24116.0, 0.0, 0.0,
+            0.0, -40304.0, 7179.0, 0.0,
+            0.0, -38824.0, 34243.0, -22439.0,
+            ]).unwrap(),
+    }
+}
+
+#[rustfmt::skip]
+fn create_vip4_field() -> InternalField {
+
+    InternalField {
+
+        degree: 4,
+
+        info: None,
+
+        sv: None,
+
+        covariance: None,
+
+        g: ArcArray2::<f64>::from_shape_vec((5, 5), vec![
+            0.0, 0.0, 0.0, 0.0, 0.0,
+            420543.0, -65920.0, 0.0, 0.0, 0.0,
+            -5118.0, -61904.0, 49690.0, 0.0, 0.0,
+            -1576.0, -52036.0, 24386.0, -17597.0, 0.0,
+            -16758.0, 22210.0, -6074.0, -20243.0, 6643.0,
+            ]).unwrap(),
+
+        h: ArcArray2::<f64>::from_shape_vec((5, 5), vec![
+            0.0, 0.0, 0.0, 0.0, 0.0,
+            0.0, 24992.0, 0.0, 0.0, 0.0,
+            0.0, -36052.0, 5250.0, 0.0, 0.0,
+            0.0, -8804.0, 40829.0, -31586.0, 0.0,
+            0.0, 7557.0, 40411.0, 16597.0, 3866.0,
+            ]).unwrap(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -289,4 +1346,654 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_internal_field_try_new() {
+        use crate::error::Error;
+        use crate::internal::InternalField;
+        use ndarray::Array2;
+
+        let val = InternalField::try_new("JRM99", None, None, None);
+        assert!(
+            matches!(val, Err(Error::UnknownModel { .. })),
+            "Internal Field Error Test Failed"
+        );
+
+        let val = InternalField::try_new("Custom", None, None, None);
+        assert!(
+            matches!(val, Err(Error::MissingInput(_))),
+            "Internal Field Error Test Failed"
+        );
+
+        let val = InternalField::try_new(
+            "Custom",
+            Some(Array2::zeros((3, 2))),
+            Some(Array2::zeros((3, 2))),
+            None,
+        );
+        assert!(
+            matches!(val, Err(Error::InvalidCoefficients(_))),
+            "Internal Field Error Test Failed"
+        );
+
+        let val = InternalField::try_new(
+            "Custom",
+            Some(Array2::zeros((3, 3))),
+            Some(Array2::zeros((2, 2))),
+            None,
+        );
+        assert!(
+            matches!(val, Err(Error::InvalidCoefficients(_))),
+            "Internal Field Error Test Failed"
+        );
+    }
+
+    #[test]
+    fn test_calc_internal_field_array() {
+        use crate::field::Field;
+        use crate::internal::InternalField;
+
+        let internal_field = InternalField::new("JRM33", None, None, None);
+
+        // The array and ndarray paths must agree, including after a lower degree field has used
+        // the same thread's workspace.
+        let val = internal_field.calc_field_array(3., 0.7, 2.1);
+        InternalField::new("JRM09", None, None, Some(2)).calc_field_array(5., 0.2, 0.1);
+        let val_test = internal_field.calc_field(3., 0.7, 2.1);
+
+        for i in 0..3 {
+            assert!(
+                val[i] == val_test[i],
+                "Internal Field Array Test Failed: \n Calculated {:?}, Expected {:?}",
+                val,
+                val_test
+            );
+        }
+
+        let val = internal_field.calc_field_xyz_array(-2., 1.5, 0.5);
+        let val_test = internal_field.calc_field_xyz(-2., 1.5, 0.5);
+
+        for i in 0..3 {
+            assert!(
+                val[i] == val_test[i],
+                "Internal Field Array Test Failed: \n Calculated {:?}, Expected {:?}",
+                val,
+                val_test
+            );
+        }
+    }
+
+    #[test]
+    fn test_calc_internal_field_grid() {
+        use crate::error::Error;
+        use crate::field::Field;
+        use crate::internal::InternalField;
+        use ndarray::Array1;
+        use std::f64::consts::PI;
+
+        let internal_field = InternalField::new("JRM33", None, None, Some(18));
+
+        let thetas = Array1::linspace(0., PI, 7);
+        let phis = Array1::linspace(0., 2. * PI, 9);
+        let val = internal_field.calc_field_grid(1.2, thetas.view(), phis.view());
+
+        assert!(
+            val.shape() == [7, 9, 3],
+            "Internal Field Grid Test Failed: \n Calculated shape {:?}",
+            val.shape()
+        );
+
+        for (i, &theta) in thetas.iter().enumerate() {
+            for (k, &phi) in phis.iter().enumerate() {
+                let val_test = internal_field.calc_field_array(1.2, theta, phi);
+                for c in 0..3 {
+                    assert!(
+                        (val[[i, k, c]] - val_test[c]).abs() < 1e-6,
+                        "Internal Field Grid Test Failed: \n Calculated {:?}, Expected {:?}",
+                        val[[i, k, c]],
+                        val_test[c]
+                    );
+                }
+            }
+        }
+
+        let radii = Array1::from_elem(6, 1.2);
+        let val = internal_field.calc_field_grid_radii(radii.view(), thetas.view(), phis.view());
+        assert!(
+            matches!(val, Err(Error::InvalidShape(_))),
+            "Internal Field Grid Test Failed"
+        );
+    }
+
+    #[test]
+    fn test_internal_field_registry() {
+        use crate::error::Error;
+        use crate::internal::InternalField;
+
+        let val = InternalField::new("JRM33_13", None, None, None);
+        let (g, _h) = val.get_coefficients();
+        assert!(
+            g.nrows() == 14,
+            "Internal Field Registry Test Failed: \n Calculated {:?}, Expected {:?}",
+            g.nrows(),
+            14
+        );
+
+        // A lower requested degree still applies to truncated variants.
+        let val = InternalField::new("JRM33_13", None, None, Some(5));
+        assert!(
+            val.get_coefficients().0.nrows() == 6,
+            "Internal Field Registry Test Failed"
+        );
+
+        // (n, m, g, h) from Table 1 of Connerney et al. (1998).
+        let val = InternalField::new("vip4", None, None, None);
+        let (g, h) = val.get_coefficients();
+        let val_test = [
+            (1, 0, 420543., 0.),
+            (1, 1, -65920., 24992.),
+            (2, 1, -61904., -36052.),
+            (2, 2, 49690., 5250.),
+            (3, 1, -52036., -8804.),
+            (3, 2, 24386., 40829.),
+            (3, 3, -17597., -31586.),
+            (4, 1, 22210., 7557.),
+            (4, 2, -6074., 40411.),
+            (4, 3, -20243., 16597.),
+            (4, 4, 6643., 3866.),
+        ];
+        for (n, m, g_nm, h_nm) in val_test {
+            assert!(
+                g[[n, m]] == g_nm && h[[n, m]] == h_nm,
+                "Internal Field Registry Test Failed: \n Calculated {:?}, Expected {:?}",
+                (n, m, g[[n, m]], h[[n, m]]),
+                (n, m, g_nm, h_nm)
+            );
+        }
+        assert!(
+            val.model_info().map(|m| m.name.as_ref()) == Some("VIP4"),
+            "Internal Field Registry Test Failed"
+        );
+
+        let val = InternalField::try_new("VIT4", None, None, None);
+        assert!(
+            matches!(val, Err(Error::ModelNotBundled(_))),
+            "Internal Field Registry Test Failed"
+        );
+    }
+
+    #[test]
+    fn test_internal_field_secular_variation() {
+        use crate::coefficients::CoefficientFormat;
+        use crate::error::Error;
+        use crate::field::Field;
+        use crate::internal::InternalField;
+        use crate::time::Time;
+        use ndarray::{Array2, array};
+
+        let mut dg = Array2::<f64>::zeros((3, 3));
+        let mut dh = Array2::<f64>::zeros((3, 3));
+        dg[[1, 0]] = 10.;
+        dh[[2, 1]] = -4.;
+
+        let field = InternalField::new("JRM09", None, None, None)
+            .with_secular_variation(dg, dh, Time::DecimalYear(2020.))
+            .unwrap();
+
+        let evolved = field.at_time(Time::DecimalYear(2022.5));
+        let (g, h) = evolved.get_coefficients();
+        let (g_test, h_test) = field.get_coefficients();
+        assert!(
+            (g[[1, 0]] - g_test[[1, 0]] - 25.).abs() < 1e-6
+                && (h[[2, 1]] - h_test[[2, 1]] + 10.).abs() < 1e-6
+                && (g[[3, 1]] - g_test[[3, 1]]).abs() < 1e-6,
+            "Secular Variation Test Failed: \n Calculated {:?}, Expected {:?}",
+            (g[[1, 0]] - g_test[[1, 0]], h[[2, 1]] - h_test[[2, 1]]),
+            (25., -10.)
+        );
+        assert!(
+            evolved.epoch() == Some(2022.5),
+            "Secular Variation Test Failed"
+        );
+
+        // Evaluating at a time must match evaluating the evolved model.
+        let time = Time::J2000Seconds(7e8);
+        let val = field.calc_field_at_time(4., 1.1, 0.3, time);
+        let val_test = field.at_time(time).calc_field_array(4., 1.1, 0.3);
+        for i in 0..3 {
+            assert!(
+                (val[i] - val_test[i]).abs() < 1e-9,
+                "Secular Variation Test Failed: \n Calculated {:?}, Expected {:?}",
+                val,
+                val_test
+            );
+        }
+
+        // The maps need one time per position.
+        let positions = array![[4., 1.1, 0.3], [5., 0.4, 2.0]];
+        let val = field
+            .parmap_calc_field_at_time(positions.view(), array![2021., 2023.].view())
+            .unwrap();
+        let val_test = field.calc_field_at_time(5., 0.4, 2.0, Time::DecimalYear(2023.));
+        assert!(
+            val.row(1).to_vec() == val_test,
+            "Secular Variation Test Failed: \n Calculated {:?}, Expected {:?}",
+            val.row(1),
+            val_test
+        );
+        for times in [array![2021.], array![2021., 2022., 2023.]] {
+            assert!(
+                matches!(
+                    field.map_calc_field_at_time(positions.view(), times.view()),
+                    Err(Error::InvalidShape(_))
+                ) && matches!(
+                    field.parmap_calc_field_at_time(positions.view(), times.view()),
+                    Err(Error::InvalidShape(_))
+                ),
+                "Secular Variation Test Failed"
+            );
+        }
+
+        // Secular variation survives a round trip through JSON.
+        let mut buffer = vec![];
+        field
+            .to_writer(&mut buffer, CoefficientFormat::Json)
+            .unwrap();
+        let val = InternalField::from_reader(buffer.as_slice(), CoefficientFormat::Json).unwrap();
+        let (dg, _dh) = val.get_secular_variation().unwrap();
+        assert!(
+            val.epoch() == Some(2020.) && (dg[[1, 0]] - 10.).abs() < 1e-9,
+            "Secular Variation Test Failed: \n Calculated {:?}",
+            dg
+        );
+    }
+
+    #[test]
+    fn test_internal_field_jacobian() {
+        use crate::field::{Field, numerical_jacobian_xyz};
+        use crate::internal::InternalField;
+
+        let internal_field = InternalField::new("JRM33", None, None, Some(13));
+
+        // Including a point on the polar axis, where the numerical fallback is used.
+        for (x, y, z) in [(1.2, -0.4, 0.7), (-3., 2., -5.), (0., 0., 2.)] {
+            let val = internal_field.calc_jacobian_xyz_array(x, y, z);
+            let val_test = numerical_jacobian_xyz(&internal_field, x, y, z);
+
+            let scale = val_test
+                .iter()
+                .flatten()
+                .fold(0., |acc: f64, v| acc.max(v.abs()));
+            for i in 0..3 {
+                for j in 0..3 {
+                    assert!(
+                        (val[i][j] - val_test[i][j]).abs() < 1e-6 * scale,
+                        "Internal Field Jacobian Test Failed: \n Calculated {:?}, Expected {:?}",
+                        val,
+                        val_test
+                    );
+                }
+            }
+
+            // The field is divergence free.
+            let divergence = val[0][0] + val[1][1] + val[2][2];
+            assert!(
+                divergence.abs() < 1e-9 * scale,
+                "Internal Field Divergence Test Failed: \n Calculated {:?}",
+                divergence
+            );
+        }
+    }
+
+    #[test]
+    fn test_internal_field_potential() {
+        use crate::field::Field;
+        use crate::internal::InternalField;
+
+        let internal_field = InternalField::new("JRM09", None, None, None);
+
+        // B = -grad V, checked with central differences in spherical coordinates.
+        let (r, theta, phi) = (1.7, 1.1, 4.2);
+        let step = 1e-5;
+        let v = |r, theta, phi| internal_field.calc_potential(r, theta, phi);
+
+        let val = [
+            -(v(r + step, theta, phi) - v(r - step, theta, phi)) / (2. * step),
+            -(v(r, theta + step, phi) - v(r, theta - step, phi)) / (2. * step * r),
+            -(v(r, theta, phi + step) - v(r, theta, phi - step)) / (2. * step * r * theta.sin()),
+        ];
+        let val_test = internal_field.calc_field_array(r, theta, phi);
+
+        for i in 0..3 {
+            assert!(
+                (val[i] - val_test[i]).abs() < 1e-4 * val_test[0].abs(),
+                "Internal Field Potential Test Failed: \n Calculated {:?}, Expected {:?}",
+                val,
+                val_test
+            );
+        }
+    }
+
+    #[test]
+    fn test_internal_field_power_spectrum() {
+        use crate::internal::InternalField;
+
+        let internal_field = InternalField::new("JRM09", None, None, None);
+
+        // The dipole term, 2 (g10^2 + g11^2 + h11^2), at the surface and one radius further out.
+        let dipole: f64 = 2. * (410244.7_f64.powi(2) + 71498.3_f64.powi(2) + 21330.5_f64.powi(2));
+        let val = internal_field.power_spectrum(1.);
+        let val_outer = internal_field.power_spectrum(2.);
+
+        assert!(
+            val.len() == 11 && val[0] == 0. && (val[1] / dipole - 1.).abs() < 1e-12,
+            "Internal Field Power Spectrum Test Failed: \n Calculated {:?}, Expected {:?}",
+            val[1],
+            dipole
+        );
+        assert!(
+            (val_outer[3] / val[3] - 0.5_f64.powi(10)).abs() < 1e-12,
+            "Internal Field Power Spectrum Test Failed: \n Calculated {:?}",
+            val_outer
+        );
+    }
+
+    #[test]
+    fn test_internal_field_degree_range() {
+        use crate::error::Error;
+        use crate::field::Field;
+        use crate::internal::InternalField;
+
+        let internal_field = InternalField::new("JRM33", None, None, Some(13));
+        let (r, theta, phi) = (1.3, 0.8, 2.5);
+
+        // The low and high degree parts add up to the full field.
+        let low = internal_field.degree_range(0, 4).unwrap();
+        let high = internal_field.degree_range(5, 13).unwrap();
+        let val_low = low.calc_field_array(r, theta, phi);
+        let val_high = high.calc_field_array(r, theta, phi);
+        let val_test = internal_field.calc_field_array(r, theta, phi);
+
+        for i in 0..3 {
+            assert!(
+                (val_low[i] + val_high[i] - val_test[i]).abs() < 1e-8 * val_test[0].abs(),
+                "Internal Field Degree Range Test Failed: \n Calculated {:?}, Expected {:?}",
+                [val_low, val_high],
+                val_test
+            );
+        }
+
+        // Splitting per degree keeps the coefficients of each degree.
+        let (g_test, _) = internal_field.get_coefficients();
+        let parts = internal_field.split_degrees();
+        let (g, _) = parts[2].get_coefficients();
+        assert!(
+            parts.len() == 14 && g.nrows() == 3 && g[[1, 0]] == 0. && g[[2, 1]] == g_test[[2, 1]],
+            "Internal Field Degree Range Test Failed: \n Calculated {:?}",
+            g
+        );
+
+        for (min_degree, max_degree) in [(3, 2), (0, 14)] {
+            let val = internal_field.degree_range(min_degree, max_degree);
+            assert!(
+                matches!(val, Err(Error::InvalidCoefficients(_))),
+                "Internal Field Degree Range Test Failed"
+            );
+        }
+    }
+
+    #[test]
+    fn test_internal_field_rotate() {
+        use crate::convert;
+        use crate::field::Field;
+        use crate::internal::InternalField;
+
+        let internal_field = InternalField::new("JRM09", None, None, None);
+        let rotation = convert::rot_matrix_z(0.3)
+            .dot(&convert::rot_matrix_y(1.2))
+            .dot(&convert::rot_matrix_x(-2.1));
+        let rotated = internal_field.rotate(rotation.view()).unwrap();
+
+        // B_new(R x) = R B(x)
+        let x = ndarray::array![0.4, -1.1, 0.9];
+        let x_new = rotation.dot(&x);
+        let val = rotated.calc_field_xyz(x_new[0], x_new[1], x_new[2]);
+        let val_test = rotation.dot(&internal_field.calc_field_xyz(x[0], x[1], x[2]));
+
+        for i in 0..3 {
+            assert!(
+                (val[i] - val_test[i]).abs() < 1e-8 * val_test[0].abs(),
+                "Internal Field Rotation Test Failed: \n Calculated {:?}, Expected {:?}",
+                val,
+                val_test
+            );
+        }
+
+        // The power spectrum is invariant under rotations.
+        let (val, val_test) = (
+            rotated.power_spectrum(1.),
+            internal_field.power_spectrum(1.),
+        );
+        for i in 0..11 {
+            assert!(
+                (val[i] - val_test[i]).abs() <= 1e-9 * val_test[i],
+                "Internal Field Rotation Test Failed: \n Calculated {:?}, Expected {:?}",
+                val,
+                val_test
+            );
+        }
+    }
+
+    #[test]
+    fn test_internal_field_dipole_frame() {
+        use crate::error::Error;
+        use crate::internal::InternalField;
+        use ndarray::Array2;
+
+        let internal_field = InternalField::new("JRM33", None, None, Some(13));
+        let dipole = internal_field.dipole().unwrap();
+        let (g, h) = internal_field.in_dipole_frame().unwrap().get_coefficients();
+
+        assert!(
+            g[[1, 1]].abs() < 1e-6
+                && h[[1, 1]].abs() < 1e-6
+                && (g[[1, 0]] - dipole.b0).abs() < 1e-6,
+            "Internal Field Dipole Frame Test Failed: \n Calculated {:?}, {:?}",
+            g.row(1),
+            h.row(1)
+        );
+
+        let val = internal_field.rotate(Array2::from_elem((3, 3), 1.).view());
+        assert!(
+            matches!(val, Err(Error::InvalidRotation(_))),
+            "Internal Field Rotation Test Failed"
+        );
+    }
+
+    #[test]
+    fn test_internal_field_polar_orbit() {
+        use crate::convert;
+        use crate::field::Field;
+        use crate::internal::InternalField;
+        use std::f64::consts::FRAC_PI_2;
+
+        let internal_field = InternalField::new("JRM33", None, None, Some(13));
+
+        // The same model rotated so that the poles lie on the equator gives reference values
+        // that do not involve the polar limit.
+        let rotation = convert::rot_matrix_x(FRAC_PI_2);
+        let rotated = internal_field.rotate(rotation.view()).unwrap();
+        let reference = |x: f64, y: f64, z: f64| {
+            let x_rot = rotation.dot(&ndarray::array![x, y, z]);
+            let b_rot = rotated.calc_field_xyz(x_rot[0], x_rot[1], x_rot[2]);
+            rotation.t().dot(&b_rot)
+        };
+
+        // A polar pass along the 0.3 rad meridian, through both poles.
+        for (r, z_sign) in [(1.1, 1.), (2.5, -1.)] {
+            for theta in [-0.1, -1e-6, -1e-12, 0., 1e-12, 1e-6, 0.1] {
+                let (x, y, z) = (
+                    r * f64::sin(theta) * 0.3_f64.cos(),
+                    r * f64::sin(theta) * 0.3_f64.sin(),
+                    z_sign * r * f64::cos(theta),
+                );
+                let val = internal_field.calc_field_xyz(x, y, z);
+                let val_test = reference(x, y, z);
+
+                for i in 0..3 {
+                    assert!(
+                        (val[i] - val_test[i]).abs() < 1e-9 * val_test[2].abs(),
+                        "Internal Field Polar Orbit Test Failed: \n Calculated {:?}, Expected {:?}",
+                        val,
+                        val_test
+                    );
+                }
+            }
+        }
+
+        // Exactly on the pole, Btheta and Bphi are the limits along the requested meridian.
+        let phi = 1.2;
+        let val = internal_field.calc_field(2., 0., phi);
+        let b_xyz = reference(0., 0., 2.);
+        let val_test = [
+            b_xyz[2],
+            b_xyz[0] * phi.cos() + b_xyz[1] * phi.sin(),
+            -b_xyz[0] * phi.sin() + b_xyz[1] * phi.cos(),
+        ];
+
+        for i in 0..3 {
+            assert!(
+                val_test[2].abs() > 1. && (val[i] - val_test[i]).abs() < 1e-9 * val_test[0].abs(),
+                "Internal Field Pole Test Failed: \n Calculated {:?}, Expected {:?}",
+                val,
+                val_test
+            );
+        }
+    }
+
+    #[test]
+    fn test_internal_field_high_degree() {
+        use crate::field::Field;
+        use crate::internal::InternalField;
+        use ndarray::Array2;
+
+        // A single zonal term of degree 1200, well past where the Gauss normalization overflows.
+        // Br = (n + 1) P(n, 0), with P(1200, 0)(cos 1) from arbitrary precision arithmetic.
+        let degree = 1200;
+        let mut g = Array2::<f64>::zeros((degree + 1, degree + 1));
+        g[[degree, 0]] = 1.;
+        let internal_field = InternalField::new(
+            "Custom",
+            Some(g),
+            Some(Array2::zeros((degree + 1, degree + 1))),
+            None,
+        );
+
+        let val = internal_field.calc_field(1., 1., 0.4);
+        let val_test = 1201. * 0.023369751519969798;
+        assert!(
+            (val[0] / val_test - 1.).abs() < 1e-10 && val[2] == 0.,
+            "Internal Field High Degree Test Failed: \n Calculated {:?}, Expected {:?}",
+            val,
+            val_test
+        );
+    }
+
+    #[test]
+    fn test_internal_field_uncertainty() {
+        use crate::convert;
+        use crate::field::Field;
+        use crate::internal::{InternalField, coefficient_index};
+        use ndarray::{Array1, Array2};
+
+        let internal_field = InternalField::new("JRM09", None, None, None);
+        let (g, h) = internal_field.get_coefficients();
+
+        // The field is the product of the design matrix rows with the coefficients.
+        let mut coefficients = Array1::<f64>::zeros(10 * 12);
+        for n in 1..11 {
+            for m in 0..n + 1 {
+                coefficients[coefficient_index(n, m, false)] = g[[n, m]];
+                if m > 0 {
+                    coefficients[coefficient_index(n, m, true)] = h[[n, m]];
+                }
+            }
+        }
+        let (r, theta, phi) = (2., 0.7, 1.3);
+        let val = internal_field
+            .design_matrix_rows(r, theta, phi)
+            .dot(&coefficients);
+        let val_test = internal_field.calc_field(r, theta, phi);
+        for i in 0..3 {
+            assert!(
+                (val[i] - val_test[i]).abs() < 1e-9 * val_test[0].abs(),
+                "Internal Field Design Matrix Test Failed: \n Calculated {:?}, Expected {:?}",
+                val,
+                val_test
+            );
+        }
+
+        assert!(
+            internal_field
+                .calc_field_uncertainty(r, theta, phi)
+                .is_err()
+                && internal_field
+                    .parmap_calc_field_uncertainty(Array2::ones((4, 3)).view())
+                    .is_err()
+                && internal_field
+                    .clone()
+                    .with_covariance(Array2::eye(3))
+                    .is_err(),
+            "Internal Field Uncertainty Test Failed"
+        );
+
+        // An uncertainty on g10 alone gives the field of a unit axial dipole.
+        let mut sigma_g = Array2::<f64>::zeros((2, 2));
+        sigma_g[[1, 0]] = 1.;
+        let val = internal_field
+            .clone()
+            .with_standard_deviations(sigma_g, Array2::zeros((2, 2)))
+            .unwrap()
+            .calc_field_uncertainty(r, theta, phi)
+            .unwrap();
+        let val_test = [2. * theta.cos() / 8., theta.sin() / 8., 0.];
+        for i in 0..3 {
+            assert!(
+                (val[i] - val_test[i]).abs() < 1e-12,
+                "Internal Field Uncertainty Test Failed: \n Calculated {:?}, Expected {:?}",
+                val,
+                val_test
+            );
+        }
+
+        // The total variance is independent of the frame, so it carries over to the rotated
+        // model at the rotated position.
+        let sigma = Array2::from_shape_fn((11, 11), |(n, m)| 1. + 0.1 * n as f64 + 0.3 * m as f64);
+        let internal_field = internal_field
+            .with_standard_deviations(sigma.clone(), sigma)
+            .unwrap();
+        let rotation = convert::rot_matrix_iau_to_mag(0.3, 2.1);
+        let rotated = internal_field.rotate(rotation.view()).unwrap();
+
+        let x = ndarray::array![0.4, -1.2, 0.9];
+        let x_rot = rotation.dot(&x);
+        let pos = convert::pos_xyz_to_rtp_array([x[0], x[1], x[2]]);
+        let pos_rot = convert::pos_xyz_to_rtp_array([x_rot[0], x_rot[1], x_rot[2]]);
+        let val = internal_field
+            .calc_field_uncertainty(pos[0], pos[1], pos[2])
+            .unwrap();
+        let val_rot = rotated
+            .calc_field_uncertainty(pos_rot[0], pos_rot[1], pos_rot[2])
+            .unwrap();
+        let (total, total_rot) = (
+            val.iter().map(|x| x * x).sum::<f64>(),
+            val_rot.iter().map(|x| x * x).sum::<f64>(),
+        );
+        assert!(
+            (total / total_rot - 1.).abs() < 1e-9,
+            "Internal Field Rotated Uncertainty Test Failed: \n Calculated {:?}, Expected {:?}",
+            total_rot,
+            total
+        );
+    }
 }
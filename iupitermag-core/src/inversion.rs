@@ -0,0 +1,454 @@
+use ndarray::parallel::prelude::*;
+use ndarray::{Array1, Array2, Array3, ArrayView1, ArrayView2, Zip, s};
+
+use crate::error::Error;
+use crate::field::Field;
+use crate::internal::{InternalField, coefficient_index, fill_design_rows};
+use crate::legendre::LEGENDRE_WORKSPACE;
+
+/// Number of positions per block when accumulating the normal equations, so that the full
+/// design matrix never has to be held in memory.
+const BLOCK_SIZE: usize = 256;
+
+/// Regularization added to the least-squares misfit, as lambda times a quadratic norm of the
+/// coefficients.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Regularization {
+    /// Plain least squares.
+    None,
+    /// Damped least squares (Tikhonov), penalizing lambda sum (g_n^m^2 + h_n^m^2).
+    Tikhonov(f64),
+    /// Minimum norm solution, penalizing lambda times the mean square field over the sphere of
+    /// radius `radius` (in planetary radii), which is the sum over n of
+    /// [`InternalField::power_spectrum`]. A radius below 1, e.g. at the top of the dynamo region,
+    /// damps the high degrees most strongly.
+    FieldNorm {
+        /// Weight of the norm relative to the misfit in nT^2.
+        lambda: f64,
+        /// Radius of the sphere over which the field is averaged.
+        radius: f64,
+    },
+}
+
+impl Regularization {
+    /// Diagonal of the regularization matrix for the coefficients up to `degree`, in the order of
+    /// [`coefficient_index`].
+    fn diagonal(&self, degree: usize) -> Array1<f64> {
+        let mut diagonal = Array1::<f64>::zeros(degree * (degree + 2));
+
+        for n in 1..degree + 1 {
+            let weight = match *self {
+                Regularization::None => 0.,
+                Regularization::Tikhonov(lambda) => lambda,
+                Regularization::FieldNorm { lambda, radius } => {
+                    lambda * (n + 1) as f64 * radius.powi(-2 * n as i32 - 4)
+                }
+            };
+            let start = coefficient_index(n, 0, false);
+            diagonal
+                .slice_mut(s![start..start + 2 * n + 1])
+                .fill(weight);
+        }
+        diagonal
+    }
+}
+
+/// Result of [`Inversion::solve`].
+#[derive(Clone)]
+pub struct InversionResult {
+    /// The fitted model, with the covariance of its coefficients attached, see
+    /// [`InternalField::calc_field_uncertainty`].
+    pub field: InternalField,
+    /// Residuals of (Br, Btheta, Bphi) in nT, observed minus modelled, of shape (N, 3). The
+    /// modelled field includes the subtracted field, if any.
+    pub residuals: Array2<f64>,
+    /// Root mean square of the residuals in nT.
+    pub rms: f64,
+}
+
+/// Least-squares inversion of magnetic field observations for the coefficients of an internal
+/// field model, e.g.
+///
+/// ```
+/// # use iupitermag::field::Field;
+/// # use iupitermag::internal::InternalField;
+/// # use iupitermag::inversion::{Inversion, Regularization};
+/// # use ndarray::Array2;
+/// # let positions = Array2::from_shape_fn((200, 3), |(i, j)| match j {
+/// #     0 => 1.5 + (i % 7) as f64,
+/// #     1 => 0.1 + 0.015 * i as f64,
+/// #     _ => 0.37 * i as f64,
+/// # });
+/// # let observed = InternalField::new("JRM09", None, None, Some(3)).map_calc_field(positions.view());
+/// let result = Inversion::new(3)
+///     .regularization(Regularization::Tikhonov(1e-6))
+///     .solve(positions.view(), observed.view())
+///     .unwrap();
+/// assert!(result.rms < 1.);
+/// ```
+///
+/// The coefficients are found from the regularized normal equations
+/// (A^T A + lambda R) c = A^T d, where the design matrix A is given by [`design_matrix`]. The
+/// covariance of the result is s^2 (A^T A + lambda R)^-1, with s^2 the residual variance.
+#[derive(Clone)]
+pub struct Inversion<'a> {
+    degree: usize,
+    regularization: Regularization,
+    subtract: Option<&'a (dyn Field + Sync)>,
+}
+
+impl<'a> Inversion<'a> {
+    /// Set up an inversion for the coefficients up to `degree`, without regularization.
+    pub fn new(degree: usize) -> Self {
+        Inversion {
+            degree,
+            regularization: Regularization::None,
+            subtract: None,
+        }
+    }
+
+    /// Set the regularization of the inversion.
+    pub fn regularization(mut self, regularization: Regularization) -> Self {
+        self.regularization = regularization;
+        self
+    }
+
+    /// Subtract a known field, e.g. a [`crate::currentsheet::CurrentSheetField`], from the
+    /// observations before fitting the internal field to what is left.
+    pub fn subtract_field(mut self, field: &'a (dyn Field + Sync)) -> Self {
+        self.subtract = Some(field);
+        self
+    }
+
+    /// Fit the observed field `observed` (Br, Btheta, Bphi in nT, shape (N, 3)) at `positions`
+    /// ((r, theta, phi), shape (N, 3)). Returns an error if the shapes are inconsistent, there
+    /// are no observations, or the problem is singular, e.g. with too few observations and no
+    /// regularization.
+    pub fn solve(
+        &self,
+        positions: ArrayView2<f64>,
+        observed: ArrayView2<f64>,
+    ) -> Result<InversionResult, Error> {
+        if positions.ncols() != 3 || observed.dim() != positions.dim() {
+            return Err(Error::InvalidShape(format!(
+                "positions and observations must both have shape (N, 3), got {:?} and {:?}",
+                positions.shape(),
+                observed.shape()
+            )));
+        }
+        if positions.nrows() == 0 {
+            return Err(Error::InvalidShape(
+                "at least one observation is needed".to_string(),
+            ));
+        }
+        if self.degree == 0 {
+            return Err(Error::Inversion(
+                "the degree of the inversion must be at least 1".to_string(),
+            ));
+        }
+
+        let mut data = observed.to_owned();
+        if let Some(field) = self.subtract {
+            Zip::from(data.rows_mut())
+                .and(positions.rows())
+                .par_for_each(|mut d, x| {
+                    let b = field.calc_field_array(x[0], x[1], x[2]);
+                    d[0] -= b[0];
+                    d[1] -= b[1];
+                    d[2] -= b[2];
+                });
+        }
+
+        // Accumulate A^T A and A^T d over blocks of positions in parallel.
+        let size = self.degree * (self.degree + 2);
+        let n_blocks = positions.nrows().div_ceil(BLOCK_SIZE);
+        let (mut normal, rhs) = (0..n_blocks)
+            .into_par_iter()
+            .map(|i| {
+                let block = s![
+                    i * BLOCK_SIZE..((i + 1) * BLOCK_SIZE).min(positions.nrows()),
+                    ..
+                ];
+                let a = design_matrix_block(self.degree, positions.slice(block));
+                let d: Array1<f64> = data.slice(block).iter().copied().collect();
+                (a.t().dot(&a), a.t().dot(&d))
+            })
+            .reduce(
+                || {
+                    (
+                        Array2::<f64>::zeros((size, size)),
+                        Array1::<f64>::zeros(size),
+                    )
+                },
+                |(a_1, b_1), (a_2, b_2)| (a_1 + a_2, b_1 + b_2),
+            );
+
+        let mut diagonal = normal.diag_mut();
+        diagonal += &self.regularization.diagonal(self.degree);
+
+        let l = cholesky(&normal).ok_or_else(|| {
+            Error::Inversion(
+                "the normal equations are singular, add observations or regularization".to_string(),
+            )
+        })?;
+        let coefficients = cholesky_solve(&l, rhs.view());
+        let (g, h) = vector_to_coefficients(coefficients.view(), self.degree);
+        let field = InternalField::try_new("Custom", Some(g), Some(h), None)?;
+
+        let residuals = data - field.parmap_calc_field(positions);
+        let sum_squares = residuals.iter().map(|x| x * x).sum::<f64>();
+        let rms = (sum_squares / residuals.len() as f64).sqrt();
+
+        let dof = residuals.len().saturating_sub(size).max(1);
+        let covariance = cholesky_inverse(&l) * (sum_squares / dof as f64);
+        let field = field.with_covariance(covariance)?;
+
+        Ok(InversionResult {
+            field,
+            residuals,
+            rms,
+        })
+    }
+}
+
+/// Build the design matrix that maps the coefficients up to `degree`, in the order of
+/// [`coefficient_index`], to the field at `positions` ((r, theta, phi), shape (N, 3)). Rows
+/// 3i, 3i + 1 and 3i + 2 are Br, Btheta and Bphi at position i, so the matrix has shape
+/// (3N, degree (degree + 2)). Rows are computed in parallel using Rayon.
+pub fn design_matrix(degree: usize, positions: ArrayView2<f64>) -> Array2<f64> {
+    let size = degree * (degree + 2);
+    let mut result = Array3::<f64>::zeros((positions.nrows(), 3, size));
+
+    Zip::from(result.outer_iter_mut())
+        .and(positions.rows())
+        .par_for_each(|rows, x| {
+            LEGENDRE_WORKSPACE.with_borrow_mut(|workspace| {
+                workspace.compute(x[1], degree);
+                fill_design_rows(workspace, degree, x[0], x[2], rows);
+            });
+        });
+
+    result
+        .into_shape_with_order((positions.nrows() * 3, size))
+        .unwrap()
+}
+
+/// Serial version of [`design_matrix`], for one block of the normal equations.
+fn design_matrix_block(degree: usize, positions: ArrayView2<f64>) -> Array2<f64> {
+    let size = degree * (degree + 2);
+    let mut result = Array3::<f64>::zeros((positions.nrows(), 3, size));
+
+    LEGENDRE_WORKSPACE.with_borrow_mut(|workspace| {
+        for (rows, x) in result.outer_iter_mut().zip(positions.rows()) {
+            workspace.compute(x[1], degree);
+            fill_design_rows(workspace, degree, x[0], x[2], rows);
+        }
+    });
+
+    result
+        .into_shape_with_order((positions.nrows() * 3, size))
+        .unwrap()
+}
+
+/// Flatten the Schmidt coefficients g and h into a vector in the order of [`coefficient_index`].
+/// Degree 0 is left out.
+pub fn coefficients_to_vector(g: &Array2<f64>, h: &Array2<f64>) -> Array1<f64> {
+    let degree = g.nrows() - 1;
+    let mut coefficients = Array1::<f64>::zeros(degree * (degree + 2));
+
+    for n in 1..degree + 1 {
+        for m in 0..n + 1 {
+            coefficients[coefficient_index(n, m, false)] = g[[n, m]];
+            if m > 0 {
+                coefficients[coefficient_index(n, m, true)] = h[[n, m]];
+            }
+        }
+    }
+    coefficients
+}
+
+/// Inverse of [`coefficients_to_vector`], returning (g, h) of shape (degree + 1, degree + 1).
+pub fn vector_to_coefficients(
+    coefficients: ArrayView1<f64>,
+    degree: usize,
+) -> (Array2<f64>, Array2<f64>) {
+    let mut g = Array2::<f64>::zeros((degree + 1, degree + 1));
+    let mut h = Array2::<f64>::zeros((degree + 1, degree + 1));
+
+    for n in 1..degree + 1 {
+        for m in 0..n + 1 {
+            g[[n, m]] = coefficients[coefficient_index(n, m, false)];
+            if m > 0 {
+                h[[n, m]] = coefficients[coefficient_index(n, m, true)];
+            }
+        }
+    }
+    (g, h)
+}
+
+/// Cholesky factor L of a symmetric positive definite matrix, with `a` = L L^T. Returns `None`
+/// if the matrix is not positive definite.
+pub(crate) fn cholesky(a: &Array2<f64>) -> Option<Array2<f64>> {
+    let size = a.nrows();
+    let mut l = Array2::<f64>::zeros((size, size));
+
+    for j in 0..size {
+        let pivot = a[[j, j]] - l.row(j).slice(s![..j]).dot(&l.row(j).slice(s![..j]));
+        if pivot <= 0. || !pivot.is_finite() {
+            return None;
+        }
+        l[[j, j]] = pivot.sqrt();
+
+        for i in j + 1..size {
+            let value = a[[i, j]] - l.row(i).slice(s![..j]).dot(&l.row(j).slice(s![..j]));
+            l[[i, j]] = value / l[[j, j]];
+        }
+    }
+    Some(l)
+}
+
+/// Solve L L^T x = b for the Cholesky factor `l` from [`cholesky`].
+pub(crate) fn cholesky_solve(l: &Array2<f64>, b: ArrayView1<f64>) -> Array1<f64> {
+    let size = l.nrows();
+    let mut x = b.to_owned();
+
+    // Forward substitution with L, then back substitution with L^T.
+    for i in 0..size {
+        x[i] = (x[i] - l.row(i).slice(s![..i]).dot(&x.slice(s![..i]))) / l[[i, i]];
+    }
+    for i in (0..size).rev() {
+        x[i] = (x[i] - l.column(i).slice(s![i + 1..]).dot(&x.slice(s![i + 1..]))) / l[[i, i]];
+    }
+    x
+}
+
+/// Inverse of L L^T for the Cholesky factor `l` from [`cholesky`]. The result is exactly
+/// symmetric.
+pub(crate) fn cholesky_inverse(l: &Array2<f64>) -> Array2<f64> {
+    let size = l.nrows();
+    let mut inverse = Array2::<f64>::zeros((size, size));
+
+    for j in 0..size {
+        let mut unit = Array1::<f64>::zeros(size);
+        unit[j] = 1.;
+        inverse
+            .column_mut(j)
+            .assign(&cholesky_solve(l, unit.view()));
+    }
+    (&inverse + &inverse.t()) * 0.5
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_inversion_recovers_model() {
+        use crate::currentsheet::CurrentSheetField;
+        use crate::error::Error;
+        use crate::field::Field;
+        use crate::internal::InternalField;
+        use crate::inversion::{Inversion, Regularization};
+        use ndarray::{Array2, s};
+
+        // Noise-free observations of JRM09 to degree 4 plus the current sheet, scattered over
+        // radii and latitudes.
+        let positions = Array2::from_shape_fn((400, 3), |(i, j)| match j {
+            0 => 2. + (i % 9) as f64,
+            1 => 0.05 + 3. * (i as f64 / 400.),
+            _ => 0.77 * i as f64,
+        });
+        let internal_field = InternalField::new("JRM09", None, None, Some(4));
+        let currentsheet_field = CurrentSheetField::new(
+            "CON2020".to_string(),
+            None,
+            crate::currentsheet::IntegrationType::Analytic,
+        );
+        let observed = internal_field.map_calc_field(positions.view())
+            + currentsheet_field.map_calc_field(positions.view());
+
+        let result = Inversion::new(4)
+            .subtract_field(&currentsheet_field)
+            .solve(positions.view(), observed.view())
+            .unwrap();
+
+        let (g, h) = result.field.get_coefficients();
+        let (g_test, h_test) = internal_field.get_coefficients();
+        let error = (&g - &g_test)
+            .iter()
+            .chain((&h - &h_test).iter())
+            .fold(0., |acc: f64, x| acc.max(x.abs()));
+        assert!(
+            error < 1e-4 && result.rms < 1e-6,
+            "Inversion Test Failed: \n Calculated {:?}, Expected {:?}",
+            g,
+            g_test
+        );
+
+        // Regularization pulls the solution towards zero, most strongly at high degree for the
+        // field norm below the surface.
+        let damped = Inversion::new(4)
+            .subtract_field(&currentsheet_field)
+            .regularization(Regularization::FieldNorm {
+                lambda: 1e3,
+                radius: 0.8,
+            })
+            .solve(positions.view(), observed.view())
+            .unwrap();
+        let spectrum = damped.field.power_spectrum(1.);
+        let spectrum_test = internal_field.power_spectrum(1.);
+        assert!(
+            spectrum[4] < spectrum_test[4] && damped.rms > result.rms,
+            "Inversion Regularization Test Failed: \n Calculated {:?}, Expected {:?}",
+            spectrum,
+            spectrum_test
+        );
+
+        // Too few observations without regularization.
+        assert!(
+            Inversion::new(4)
+                .solve(positions.slice(s![..3, ..]), observed.slice(s![..3, ..]))
+                .is_err(),
+            "Inversion Test Failed"
+        );
+
+        // No observations, even with regularization, or observations of the wrong shape.
+        for (positions, observed) in [
+            (positions.slice(s![..0, ..]), observed.slice(s![..0, ..])),
+            (positions.view(), observed.slice(s![.., ..2])),
+        ] {
+            let val = Inversion::new(4)
+                .regularization(Regularization::Tikhonov(1.))
+                .solve(positions, observed);
+            assert!(
+                matches!(val, Err(Error::InvalidShape(_))),
+                "Inversion Test Failed"
+            );
+        }
+    }
+
+    #[test]
+    fn test_design_matrix() {
+        use crate::field::Field;
+        use crate::internal::InternalField;
+        use crate::inversion::{coefficients_to_vector, design_matrix, vector_to_coefficients};
+        use ndarray::array;
+
+        let internal_field = InternalField::new("JRM09", None, None, None);
+        let (g, h) = internal_field.get_coefficients();
+        let coefficients = coefficients_to_vector(&g, &h);
+
+        let (g_test, h_test) = vector_to_coefficients(coefficients.view(), 10);
+        assert!(g == g_test && h == h_test, "Coefficient Vector Test Failed");
+
+        let positions = array![[1.2, 0.3, 4.], [5., 2.9, -1.]];
+        let val = design_matrix(10, positions.view()).dot(&coefficients);
+        let val_test = internal_field.map_calc_field(positions.view());
+        for (v, v_test) in val.iter().zip(val_test.iter()) {
+            assert!(
+                (v - v_test).abs() < 1e-9 * v_test.abs().max(1.),
+                "Design Matrix Test Failed: \n Calculated {:?}, Expected {:?}",
+                val,
+                val_test
+            );
+        }
+    }
+}
@@ -1,44 +1,233 @@
 use ndarray::Array2;
+use std::cell::RefCell;
+use std::f64::consts::{FRAC_1_SQRT_2, SQRT_2};
+
+thread_local! {
+    // Per-thread Legendre storage, so that field evaluations do not allocate once warmed up.
+    pub(crate) static LEGENDRE_WORKSPACE: RefCell<LegendreWorkspace> =
+        RefCell::new(LegendreWorkspace::new(0));
+}
+
+/// Exponent of the power of two that scales the recursion in [`LegendreWorkspace::compute`].
+/// Exact in floating point, and large enough that the sectoral terms sin(theta)^m do not underflow
+/// before the recursion over the degree brings them back up, up to degree ~3000 (Holmes and
+/// Featherstone, 2002, J. Geod. 76, 279-299).
+const SCALE_EXPONENT: i32 = 900;
 
 /// Calculate the Gauss-normalized Legendre polynomials.
 /// Returns two numpy::ndarrays of size (degree + 1, degree + 1) containing the
 /// coefficient of the polynomial and its derivative (P(n, m) and dP(n, m))
 /// at order n and degree m.
+///
+/// The Gauss normalization factors themselves overflow past degree ~150, so high degree
+/// expansions should use [`schmidt_legendre_poly`] instead.
 pub fn assoc_legendre_poly(theta: &f64, degree: &usize) -> (Array2<f64>, Array2<f64>) {
-    let size = degree + 1;
+    let s = schmidt_semi_normalization_constants(degree);
+    let (p, dp) = schmidt_legendre_poly(theta, degree);
+    (p / &s, dp / &s)
+}
+
+/// Calculate the Schmidt semi-normalized Legendre polynomials.
+/// Returns two arrays of size (degree + 1, degree + 1) containing P(n, m) and dP(n, m) / dtheta
+/// at degree n and order m. These stay accurate to degree ~3000 at all colatitudes.
+pub fn schmidt_legendre_poly(theta: &f64, degree: &usize) -> (Array2<f64>, Array2<f64>) {
+    let mut workspace = LegendreWorkspace::new(*degree);
+    workspace.compute(*theta, *degree);
+    (workspace.p, workspace.dp)
+}
+
+/// Reusable storage for the Schmidt semi-normalized Legendre polynomials and their derivatives, so
+/// that repeated evaluations do not allocate. The storage grows as needed when a larger degree is
+/// requested, and is never shrunk.
+#[derive(Clone, Debug)]
+pub struct LegendreWorkspace {
+    p: Array2<f64>,
+    dp: Array2<f64>,
+    d2p: Array2<f64>,
+    p_over_sin: Array2<f64>,
+    alpha: Array2<f64>,
+    beta: Array2<f64>,
+    sqrt_int: Vec<f64>,
+}
+
+impl LegendreWorkspace {
+    /// Create a workspace with room for polynomials up to `degree`.
+    pub fn new(degree: usize) -> Self {
+        let size = degree + 1;
+        let sqrt_int: Vec<f64> = (0..2 * size + 1).map(|i| (i as f64).sqrt()).collect();
+        let mut alpha = Array2::<f64>::zeros((size, size));
+        let mut beta = Array2::<f64>::zeros((size, size));
+
+        // The recursion constants only depend on the degree and order, so they are computed once.
+        // Below the diagonal, P(n, m) = alpha cos(theta) P(n - 1, m) - beta P(n - 2, m), and the
+        // diagonal holds the factor of the sectoral recursion P(n, n) = alpha sin(theta)
+        // P(n - 1, n - 1).
+        for i in 1..size {
+            for j in 0..i {
+                let root = sqrt_int[i + j] * sqrt_int[i - j];
+                alpha[[i, j]] = (2 * i - 1) as f64 / root;
+                beta[[i, j]] = sqrt_int[i - 1 + j] * sqrt_int[i - 1 - j] / root;
+            }
+            alpha[[i, i]] = if i == 1 {
+                1.
+            } else {
+                ((2 * i - 1) as f64 / (2 * i) as f64).sqrt()
+            };
+        }
+
+        LegendreWorkspace {
+            p: Array2::<f64>::zeros((size, size)),
+            dp: Array2::<f64>::zeros((size, size)),
+            d2p: Array2::<f64>::zeros((0, 0)),
+            p_over_sin: Array2::<f64>::zeros((size, size)),
+            alpha,
+            beta,
+            sqrt_int,
+        }
+    }
+
+    /// The largest degree that can be computed without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.p.nrows() - 1
+    }
+
+    /// Compute P(n, m), dP(n, m) and P(n, m) / sin(theta) at colatitude `theta` for all
+    /// n <= `degree`. Entries above `degree` are left untouched.
+    pub fn compute(&mut self, theta: f64, degree: usize) {
+        if degree > self.capacity() {
+            *self = LegendreWorkspace::new(degree);
+        }
+
+        let size = degree + 1;
+        let stride = self.p.ncols();
+        // The arrays are created in standard layout and never reshaped, so they are contiguous.
+        let p = self.p.as_slice_mut().unwrap();
+        let p_over_sin = self.p_over_sin.as_slice_mut().unwrap();
+        let alpha = self.alpha.as_slice().unwrap();
+        let beta = self.beta.as_slice().unwrap();
+
+        let (sintheta, costheta) = theta.sin_cos();
+        let scale = 2_f64.powi(SCALE_EXPONENT);
 
-    let mut p = Array2::<f64>::zeros((size, size));
-    let mut dp = Array2::<f64>::zeros((size, size));
-    let mut k = Array2::<f64>::zeros((size, size));
+        // The recursion runs on values scaled by 2^SCALE_EXPONENT. The Schmidt polynomials are
+        // bounded by 1, so this cannot overflow, and it keeps the small sectoral terms near the
+        // poles representable until they grow again with the degree.
+        p[0] = scale;
+        p_over_sin[0] = 0.;
 
-    let sintheta = theta.sin();
-    let costheta = theta.cos();
+        if size > 1 {
+            p[stride] = costheta * scale;
+            p[stride + 1] = sintheta * scale;
 
-    // We'd like to do the special cases manually since (i - 2) isn't defined.
-    p[[0, 0]] = 1.;
-    dp[[0, 0]] = 0.;
+            // P(n, m) / sin(theta) for m >= 1 follows the same recursion as P(n, m), starting
+            // from P(1, 1) / sin(theta) = 1. This gives the correct limit on the poles, where
+            // both P(n, m) and sin(theta) vanish, without any division.
+            p_over_sin[stride] = 0.;
+            p_over_sin[stride + 1] = scale;
+        }
 
-    p[[1, 1]] = sintheta * p[[0, 0]];
-    p[[1, 0]] = costheta * p[[0, 0]];
-    dp[[1, 1]] = sintheta * dp[[0, 0]] + costheta * p[[0, 0]];
-    dp[[1, 0]] = costheta * dp[[0, 0]] - sintheta * p[[0, 0]];
+        // General cases, working on flat row offsets to avoid repeated index calculations. The
+        // term in P(n - 2, n - 1) has beta = 0, and reads the unused upper triangle.
+        for i in 2..size {
+            let row = i * stride;
+            let row_1 = row - stride;
+            let row_2 = row_1 - stride;
 
-    // General cases
-    for i in 2..size {
-        for j in 0..i {
-            k[[i, j]] = ((i - 1).pow(2) - j.pow(2)) as f64 / ((2 * i - 1) * (2 * i - 3)) as f64;
+            p[row] = alpha[row] * costheta * p[row_1] - beta[row] * p[row_2];
+            p_over_sin[row] = 0.;
 
-            p[[i, j]] = costheta * p[[i - 1, j]] - k[[i, j]] * p[[i - 2, j]];
+            for j in 1..i {
+                p[row + j] =
+                    alpha[row + j] * costheta * p[row_1 + j] - beta[row + j] * p[row_2 + j];
+                p_over_sin[row + j] = alpha[row + j] * costheta * p_over_sin[row_1 + j]
+                    - beta[row + j] * p_over_sin[row_2 + j];
+            }
 
-            dp[[i, j]] =
-                costheta * dp[[i - 1, j]] - sintheta * p[[i - 1, j]] - k[[i, j]] * dp[[i - 2, j]];
+            p[row + i] = alpha[row + i] * sintheta * p[row_1 + i - 1];
+            p_over_sin[row + i] = alpha[row + i] * sintheta * p_over_sin[row_1 + i - 1];
         }
 
-        p[[i, i]] = sintheta * p[[i - 1, i - 1]];
+        let inv_scale = 2_f64.powi(-SCALE_EXPONENT);
+        for i in 0..size {
+            let row = i * stride;
+            p[row..row + i + 1].iter_mut().for_each(|x| *x *= inv_scale);
+            p_over_sin[row..row + i + 1]
+                .iter_mut()
+                .for_each(|x| *x *= inv_scale);
+        }
 
-        dp[[i, i]] = sintheta * dp[[i - 1, i - 1]] + costheta * p[[i - 1, i - 1]];
+        differentiate(
+            p,
+            self.dp.as_slice_mut().unwrap(),
+            &self.sqrt_int,
+            stride,
+            size,
+        );
+    }
+
+    /// Same as [`LegendreWorkspace::compute`], but also computes the second derivatives
+    /// d2P(n, m) / dtheta2, which are needed for field gradients.
+    pub fn compute_second_derivative(&mut self, theta: f64, degree: usize) {
+        self.compute(theta, degree);
+
+        // Only allocated on first use, since most evaluations do not need it.
+        if self.d2p.dim() != self.p.dim() {
+            self.d2p = Array2::<f64>::zeros(self.p.dim());
+        }
+
+        differentiate(
+            self.dp.as_slice().unwrap(),
+            self.d2p.as_slice_mut().unwrap(),
+            &self.sqrt_int,
+            self.p.ncols(),
+            degree + 1,
+        );
+    }
+
+    /// The polynomials P(n, m) from the last call to [`LegendreWorkspace::compute`].
+    pub fn p(&self) -> &Array2<f64> {
+        &self.p
+    }
+
+    /// The derivatives dP(n, m) / dtheta from the last call to [`LegendreWorkspace::compute`].
+    pub fn dp(&self) -> &Array2<f64> {
+        &self.dp
+    }
+
+    /// P(n, m) / sin(theta) for m >= 1 from the last call to [`LegendreWorkspace::compute`]. This
+    /// stays finite on the poles, and the entries for m = 0 are zero.
+    pub fn p_over_sin(&self) -> &Array2<f64> {
+        &self.p_over_sin
+    }
+
+    /// The second derivatives d2P(n, m) / dtheta2 from the last call to
+    /// [`LegendreWorkspace::compute_second_derivative`].
+    pub fn d2p(&self) -> &Array2<f64> {
+        &self.d2p
+    }
+}
+
+/// Differentiate the Schmidt semi-normalized functions `f` with respect to theta, using the
+/// relation to the neighbouring orders of the same degree,
+///
+/// df(n, m) = (sqrt((n + m) (n - m + 1)) f(n, m - 1) - sqrt((n + m + 1) (n - m)) f(n, m + 1)) / 2,
+///
+/// with extra factors of sqrt(2) next to m = 0. Unlike the derivative of the recursion, this has
+/// no division by sin(theta), and it applies to dP as well to give d2P.
+fn differentiate(f: &[f64], df: &mut [f64], sqrt_int: &[f64], stride: usize, size: usize) {
+    df[0] = 0.;
+
+    for i in 1..size {
+        let row = i * stride;
+        let root = |j: usize| sqrt_int[i + j + 1] * sqrt_int[i - j];
+
+        df[row] = -root(0) * FRAC_1_SQRT_2 * f[row + 1];
+        df[row + 1] = 0.5 * (SQRT_2 * root(0) * f[row] - root(1) * f[row + 2.min(i)]);
+
+        for j in 2..i + 1 {
+            df[row + j] = 0.5 * (root(j - 1) * f[row + j - 1] - root(j) * f[row + (j + 1).min(i)]);
+        }
     }
-    (p, dp)
 }
 
 /// Calculates the `s` Schmidt normalization constant.
@@ -87,4 +276,150 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_legendre_workspace() {
+        use crate::legendre::{LegendreWorkspace, schmidt_legendre_poly};
+        use ndarray::s;
+
+        // A workspace that has already computed a larger degree must give the same values as a
+        // freshly allocated one.
+        let mut workspace = LegendreWorkspace::new(2);
+        workspace.compute(0.3, 8);
+        workspace.compute(1.1, 4);
+        assert!(workspace.capacity() == 8, "Legendre Workspace Test Failed");
+
+        let (p, dp) = schmidt_legendre_poly(&1.1, &4);
+        let p_ws = workspace.p().slice(s![..5, ..5]);
+        let dp_ws = workspace.dp().slice(s![..5, ..5]);
+
+        assert!(
+            p_ws == p && dp_ws == dp,
+            "Legendre Workspace Test Failed: \n Calculated {:?}, Expected {:?}",
+            p_ws,
+            p
+        );
+    }
+
+    #[test]
+    fn test_legendre_second_derivative() {
+        use crate::legendre::LegendreWorkspace;
+
+        // Compare against a central difference of dP.
+        let (theta, step) = (0.8, 1e-5);
+        let mut workspace = LegendreWorkspace::new(12);
+        workspace.compute(theta + step, 12);
+        let dp_plus = workspace.dp().clone();
+        workspace.compute(theta - step, 12);
+        let dp_minus = workspace.dp().clone();
+        workspace.compute_second_derivative(theta, 12);
+
+        for n in 0..13 {
+            for m in 0..n + 1 {
+                let val = workspace.d2p()[[n, m]];
+                let val_test = (dp_plus[[n, m]] - dp_minus[[n, m]]) / (2. * step);
+                assert!(
+                    (val - val_test).abs() < 1e-6,
+                    "Legendre Second Derivative Test Failed at ({n}, {m}): \n Calculated {:?}, Expected {:?}",
+                    val,
+                    val_test
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_legendre_p_over_sin() {
+        use crate::legendre::LegendreWorkspace;
+
+        let mut workspace = LegendreWorkspace::new(15);
+
+        workspace.compute(0.7, 15);
+        let sintheta = 0.7_f64.sin();
+        for n in 1..16 {
+            for m in 1..n + 1 {
+                let val = workspace.p_over_sin()[[n, m]] * sintheta;
+                let val_test = workspace.p()[[n, m]];
+                assert!(
+                    (val - val_test).abs() < 1e-12,
+                    "Legendre P / sin Test Failed at ({n}, {m}): \n Calculated {:?}, Expected {:?}",
+                    val,
+                    val_test
+                );
+            }
+        }
+
+        // On the pole, P(n, 1) / sin(theta) -> dP(n, 1) / dtheta and the higher orders vanish.
+        workspace.compute(0., 15);
+        for n in 1..16 {
+            let val = workspace.p_over_sin().row(n).to_owned();
+            assert!(
+                (val[1] - workspace.dp()[[n, 1]]).abs() < 1e-12
+                    && val.iter().skip(2).all(|x| *x == 0.),
+                "Legendre P / sin Pole Test Failed at {n}: \n Calculated {:?}",
+                val
+            );
+        }
+    }
+
+    #[test]
+    fn test_legendre_high_degree() {
+        use crate::legendre::LegendreWorkspace;
+        use std::f64::consts::FRAC_PI_2;
+
+        let degree = 2100;
+        let mut workspace = LegendreWorkspace::new(degree);
+
+        // Reference values (n, m, theta, P, dP) from arbitrary precision arithmetic. The last
+        // colatitude is where the sectoral terms underflow first without scaling.
+        let reference = [
+            (150, 75, 0.3, 1.071914762548972e-12, 2.1996307109659892e-10),
+            (500, 3, 0.001, 0.003636372997042505, 10.794898479505797),
+            (1000, 400, 1.2, 0.026389536375318474, -25.809325300922638),
+            (
+                2100,
+                760,
+                0.376727508058575,
+                0.063_574_232_899_535_2,
+                -29.335373160812626,
+            ),
+            (2100, 1, 1.5, 0.021417506961647706, -25.63829735031348),
+        ];
+        for (n, m, theta, p, dp) in reference {
+            workspace.compute(theta, n);
+            let val = [workspace.p()[[n, m]], workspace.dp()[[n, m]]];
+            assert!(
+                (val[0] / p - 1.).abs() < 1e-9 && (val[1] / dp - 1.).abs() < 1e-9,
+                "Legendre High Degree Test Failed at ({n}, {m}): \n Calculated {:?}, Expected {:?}",
+                val,
+                [p, dp]
+            );
+        }
+
+        // Across all degrees and colatitudes, the addition theorem gives sum_m P(n, m)^2 = 1 and
+        // sum_m dP(n, m)^2 + m^2 (P(n, m) / sin(theta))^2 = n (n + 1).
+        for theta in [0., 1e-3, 0.1, 0.376727508058575, 1., FRAC_PI_2, 3.] {
+            workspace.compute(theta, degree);
+            for n in 0..degree + 1 {
+                let (p, dp, p_over_sin) = (
+                    workspace.p().row(n),
+                    workspace.dp().row(n),
+                    workspace.p_over_sin().row(n),
+                );
+                let (mut sum_p, mut sum_dp) = (0., 0.);
+                for m in 0..n + 1 {
+                    sum_p += p[m] * p[m];
+                    sum_dp += dp[m] * dp[m] + (m * m) as f64 * p_over_sin[m] * p_over_sin[m];
+                }
+                let sum_dp_test = (n * (n + 1)) as f64;
+                assert!(
+                    (sum_p - 1.).abs() < 1e-10
+                        && (sum_dp - sum_dp_test).abs() <= 1e-10 * sum_dp_test,
+                    "Legendre Sum Rule Test Failed at ({theta}, {n}): \n Calculated {:?}, Expected {:?}",
+                    [sum_p, sum_dp],
+                    [1., sum_dp_test]
+                );
+            }
+        }
+    }
 }
@@ -1,20 +1,48 @@
 #![doc = include_str!("../README.md")]
 #![warn(missing_docs)]
 
+/// Bessel functions of the first kind, used by the current sheet integral.
+pub mod bessel;
+
+/// Reading and writing internal field coefficients from text and JSON files.
+pub mod coefficients;
+
 /// Contains methods useful for converting positions and vectors between coordinate systems.
 pub mod convert;
 
 /// Definitions for Jupiter's current sheet models.
 pub mod currentsheet;
 
+/// Derived properties of the dipole of an internal field model.
+pub mod dipole;
+
+/// The error type returned by fallible functions in this crate.
+pub mod error;
+pub use error::Error;
+
+/// External (magnetospheric or induced) spherical harmonic field models.
+pub mod external;
+
 /// Common module for all types of fields. Contains the `Field` trait.
 pub mod field;
 
+/// Levenberg-Marquardt fitting of current sheet parameters to observations.
+pub mod fit;
+
 /// Definitions for Jupiter's internal field models.
 pub mod internal;
 
 /// Some functions for calculating Legendre polynomials.
 pub mod legendre;
 
+/// Least-squares inversion of observations for internal field coefficients.
+pub mod inversion;
+
+/// Registry of the published internal field models and their metadata.
+pub mod models;
+
+/// Representation of times for evaluating time-dependent models.
+pub mod time;
+
 /// Methods for tracing magnetic field lines.
 pub mod trace;
@@ -0,0 +1,180 @@
+use std::borrow::Cow;
+
+/// Jupiter's equatorial radius (1 bar level) in km, used by the Juno-era models.
+pub const JUPITER_RADIUS_KM: f64 = 71492.;
+
+/// Jupiter's radius in km used by the pre-Juno Connerney models (O6, VIP4, VIT4).
+pub const JUPITER_RADIUS_CONNERNEY_KM: f64 = 71323.;
+
+/// Metadata describing a published internal field model.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModelInfo {
+    /// Name used to select the model, e.g. in [`crate::internal::InternalField::new`].
+    pub name: Cow<'static, str>,
+    /// Literature reference for the model.
+    pub reference: Cow<'static, str>,
+    /// Maximum degree of the published coefficients.
+    pub max_degree: usize,
+    /// Degree to which the authors recommend truncating the model.
+    pub recommended_degree: usize,
+    /// Planet radius in km that the model coefficients are referenced to. Positions passed to
+    /// the model should be in units of this radius.
+    pub planet_radius_km: f64,
+    /// Whether the coefficients are bundled with this crate. Models that are not bundled have
+    /// to be loaded with custom coefficients.
+    pub bundled: bool,
+}
+
+/// All the models known to this crate. The entries with `bundled: false` (VIT4, VIPAL, GSFC13ev,
+/// GSFC15evs and ISaAC) are metadata only.
+const MODELS: &[ModelInfo] = &[
+    ModelInfo {
+        name: Cow::Borrowed("JRM09"),
+        reference: Cow::Borrowed(
+            "Connerney et al. (2018), A New Model of Jupiter's Magnetic Field From Juno's First \
+             Nine Orbits, GRL 45, 2590-2596",
+        ),
+        max_degree: 10,
+        recommended_degree: 10,
+        planet_radius_km: JUPITER_RADIUS_KM,
+        bundled: true,
+    },
+    ModelInfo {
+        name: Cow::Borrowed("JRM33"),
+        reference: Cow::Borrowed(
+            "Connerney et al. (2022), A New Model of Jupiter's Magnetic Field at the Completion \
+             of Juno's Prime Mission, JGR Planets 127, e2021JE007055",
+        ),
+        max_degree: 30,
+        recommended_degree: 13,
+        planet_radius_km: JUPITER_RADIUS_KM,
+        bundled: true,
+    },
+    ModelInfo {
+        name: Cow::Borrowed("JRM33_13"),
+        reference: Cow::Borrowed(
+            "Connerney et al. (2022), JRM33 truncated at degree 13 as recommended by the authors",
+        ),
+        max_degree: 13,
+        recommended_degree: 13,
+        planet_radius_km: JUPITER_RADIUS_KM,
+        bundled: true,
+    },
+    ModelInfo {
+        name: Cow::Borrowed("O6"),
+        reference: Cow::Borrowed(
+            "Connerney (1992), Doing more with Jupiter's magnetic field, Planetary Radio \
+             Emissions III, 13-33",
+        ),
+        max_degree: 3,
+        recommended_degree: 3,
+        planet_radius_km: JUPITER_RADIUS_CONNERNEY_KM,
+        bundled: true,
+    },
+    ModelInfo {
+        name: Cow::Borrowed("VIP4"),
+        reference: Cow::Borrowed(
+            "Connerney et al. (1998), New models of Jupiter's magnetic field constrained by the \
+             Io flux tube footprint, JGR 103, 11929-11939",
+        ),
+        max_degree: 4,
+        recommended_degree: 4,
+        planet_radius_km: JUPITER_RADIUS_CONNERNEY_KM,
+        bundled: true,
+    },
+    ModelInfo {
+        name: Cow::Borrowed("VIT4"),
+        reference: Cow::Borrowed(
+            "Connerney (2007), Planetary Magnetism, Treatise on Geophysics 10, 243-280",
+        ),
+        max_degree: 4,
+        recommended_degree: 4,
+        planet_radius_km: JUPITER_RADIUS_CONNERNEY_KM,
+        bundled: false,
+    },
+    ModelInfo {
+        name: Cow::Borrowed("VIPAL"),
+        reference: Cow::Borrowed(
+            "Hess et al. (2011), Model of the Jovian magnetic field topology constrained by the \
+             Io auroral emissions, JGR 116, A05217",
+        ),
+        max_degree: 5,
+        recommended_degree: 5,
+        planet_radius_km: JUPITER_RADIUS_KM,
+        bundled: false,
+    },
+    ModelInfo {
+        name: Cow::Borrowed("GSFC13ev"),
+        reference: Cow::Borrowed(
+            "Ridley and Holme (2016), Modeling the Jovian magnetic field and its secular \
+             variation using all available magnetic field observations, JGR Planets 121, 309-337",
+        ),
+        max_degree: 13,
+        recommended_degree: 13,
+        planet_radius_km: JUPITER_RADIUS_KM,
+        bundled: false,
+    },
+    ModelInfo {
+        name: Cow::Borrowed("GSFC15evs"),
+        reference: Cow::Borrowed(
+            "Ridley and Holme (2016), Modeling the Jovian magnetic field and its secular \
+             variation using all available magnetic field observations, JGR Planets 121, 309-337",
+        ),
+        max_degree: 15,
+        recommended_degree: 15,
+        planet_radius_km: JUPITER_RADIUS_KM,
+        bundled: false,
+    },
+    ModelInfo {
+        name: Cow::Borrowed("ISaAC"),
+        reference: Cow::Borrowed(
+            "Hess et al. (2017), A model of the Jovian internal field derived from in-situ and \
+             auroral constraints, Planetary Radio Emissions VIII, 157-167",
+        ),
+        max_degree: 10,
+        recommended_degree: 10,
+        planet_radius_km: JUPITER_RADIUS_KM,
+        bundled: false,
+    },
+];
+
+/// List all the internal field models known to this crate, including the ones whose
+/// coefficients are not bundled.
+///
+/// Models with [`ModelInfo::bundled`] set to false are listed for their metadata only: selecting
+/// one by name with [`crate::internal::InternalField::try_new`] returns
+/// [`crate::Error::ModelNotBundled`], and its published coefficients have to be passed as a
+/// "Custom" field or loaded with [`crate::internal::InternalField::from_file`] instead.
+pub fn available_models() -> &'static [ModelInfo] {
+    MODELS
+}
+
+/// Look up a model by name. The lookup is case-insensitive.
+pub fn model_info(name: &str) -> Option<&'static ModelInfo> {
+    MODELS.iter().find(|m| m.name.eq_ignore_ascii_case(name))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_model_registry() {
+        use crate::models::{available_models, model_info};
+
+        let jrm33 = model_info("jrm33").expect("JRM33 should be in the registry");
+        assert!(
+            jrm33.max_degree == 30 && jrm33.recommended_degree == 13,
+            "Model Registry Test Failed: \n Calculated {:?}",
+            jrm33
+        );
+
+        assert!(model_info("JRM99").is_none(), "Model Registry Test Failed");
+
+        for model in available_models() {
+            assert!(
+                model.recommended_degree <= model.max_degree,
+                "Model Registry Test Failed: \n Calculated {:?}",
+                model
+            );
+        }
+    }
+}
@@ -0,0 +1,63 @@
+/// Number of seconds in a Julian year of 365.25 days.
+pub const SECONDS_PER_JULIAN_YEAR: f64 = 365.25 * 86400.;
+
+/// Decimal year of the J2000 epoch (2000-01-01 12:00 TT).
+const J2000_DECIMAL_YEAR: f64 = 2000.;
+
+/// A time at which to evaluate a time-dependent model.
+///
+/// Decimal years are Julian epochs, i.e. years of exactly 365.25 days counted from J2000.0. They
+/// differ from calendar fractions of a year by less than a day, which is negligible for secular
+/// variation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Time {
+    /// Decimal (Julian) year, e.g. 2021.5.
+    DecimalYear(f64),
+    /// Seconds elapsed since J2000 (2000-01-01 12:00 TT), as used by SPICE ephemeris time.
+    J2000Seconds(f64),
+}
+
+impl Time {
+    /// Return the time as a decimal (Julian) year.
+    pub fn decimal_year(&self) -> f64 {
+        match *self {
+            Time::DecimalYear(year) => year,
+            Time::J2000Seconds(seconds) => J2000_DECIMAL_YEAR + seconds / SECONDS_PER_JULIAN_YEAR,
+        }
+    }
+
+    /// Return the time as seconds elapsed since J2000.
+    pub fn j2000_seconds(&self) -> f64 {
+        match *self {
+            Time::DecimalYear(year) => (year - J2000_DECIMAL_YEAR) * SECONDS_PER_JULIAN_YEAR,
+            Time::J2000Seconds(seconds) => seconds,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_time_conversion() {
+        use crate::time::Time;
+
+        // 2016-07-05 (Juno orbit insertion) is about 5.21e8 s after J2000.
+        let val = Time::J2000Seconds(521_000_000.).decimal_year();
+        let val_test = 2016.509;
+        assert!(
+            (val - val_test).abs() < 1e-3,
+            "Time Conversion Test Failed: \n Calculated {:?}, Expected {:?}",
+            val,
+            val_test
+        );
+
+        let val = Time::DecimalYear(2021.25).j2000_seconds();
+        let val_test = 21.25 * 365.25 * 86400.;
+        assert!(
+            (val - val_test).abs() < 1e-3,
+            "Time Conversion Test Failed: \n Calculated {:?}, Expected {:?}",
+            val,
+            val_test
+        );
+    }
+}
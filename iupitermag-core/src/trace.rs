@@ -1,129 +1,432 @@
+use std::cell::Cell;
 use std::f64;
 
-use crate::currentsheet::CurrentSheetField;
+use crate::error::Error;
 use crate::field::Field;
-use crate::internal::InternalField;
 use lazyivy::{RungeKutta, RungeKuttaMethod};
-use ndarray::{Array1, Array2, ArrayView1};
+use ndarray::parallel::prelude::*;
+use ndarray::{Array1, Array2, ArrayView1, ArrayView2, Axis, concatenate, s};
 
-const R_TRACE_MAXIMUM: f64 = 200.;
+/// Adaptive Runge-Kutta schemes with an embedded error estimate that can be used for tracing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TraceMethod {
+    /// The Heun method, O(h^2), with the Euler method as the error estimator.
+    HeunEuler,
+    /// The Bogacki-Shampine method, O(h^3), with an O(h^2) error estimator.
+    BogackiShampine,
+    /// The Fehlberg method, O(h^4), with an O(h^5) error estimator.
+    Fehlberg,
+    /// The Dormand-Prince method, O(h^5), with an O(h^4) error estimator.
+    #[default]
+    DormandPrince,
+}
 
-/// Function to trace field lines from a starting position to the planet,
-/// both along and against the field. Returns the coordinates for the entire closed field line.
-pub fn trace_field_to_planet(
-    start_position: Array1<f64>,
-    internal_field: &InternalField,
-    currentsheet_field: &CurrentSheetField,
-) -> Array2<f64> {
-    let planet_field = PlanetField {
-        internal_field: internal_field.clone(),
-        currentsheet_field: currentsheet_field.clone(),
-    };
-
-    // Also keep the predicate referenced (no capture).
-    let _inside = is_inside_jupiter(start_position.view());
-
-    let absolute_tol = Array1::from_vec(vec![1.0e-4, 1.0e-4, 1.0e-4]);
-    let relative_tol = Array1::from_vec(vec![1.0e-4, 1.0e-4, 1.0e-4]);
-
-    let integrator_pos = RungeKutta::builder(
-        |_, p, mut val| val.assign(&(calc_b_unit_vector(&planet_field, p))),
-        |_, p| is_inside_jupiter(p),
-    )
-    .initial_condition(0., start_position.clone())
-    .initial_step_size(0.025)
-    .method(RungeKuttaMethod::DormandPrince, true) // `true` for adaptive step-size
-    .tolerances(absolute_tol.clone(), relative_tol.clone())
-    .set_max_step_size(0.25)
-    .build()
-    .unwrap();
-
-    let integrator_neg = RungeKutta::builder(
-        |_, p, mut val| val.assign(&(calc_b_unit_vector_inverse(&planet_field, p))),
-        |_, p| is_inside_jupiter(p),
-    )
-    .initial_condition(0., start_position.clone())
-    .initial_step_size(0.025)
-    .method(RungeKuttaMethod::DormandPrince, true) // `true` for adaptive step-size
-    .tolerances(absolute_tol, relative_tol)
-    .set_max_step_size(0.25)
-    .build()
-    .unwrap();
-
-    let trace_pos = integrator_pos.map(|(_, p)| p).collect::<Vec<Array1<f64>>>();
-    let trace_neg = integrator_neg.map(|(_, p)| p).collect::<Vec<Array1<f64>>>();
-
-    let num_points_pos = trace_pos.len();
-    let num_points_neg = trace_neg.len();
-
-    let mut result: Array2<f64> =
-        Array2::from_elem((num_points_pos + num_points_neg + 1, 3), f64::NAN);
-
-    for (i, pos) in trace_neg.iter().rev().enumerate() {
-        result.row_mut(i).assign(pos)
-    }
-
-    result.row_mut(num_points_neg).assign(&start_position);
-
-    for (i, pos) in trace_pos.iter().enumerate() {
-        result.row_mut(i + num_points_neg + 1).assign(pos);
-    }
-
-    result
+impl TraceMethod {
+    fn runge_kutta_method(self) -> RungeKuttaMethod {
+        match self {
+            Self::HeunEuler => RungeKuttaMethod::HuenEuler,
+            Self::BogackiShampine => RungeKuttaMethod::BogackiShampine,
+            Self::Fehlberg => RungeKuttaMethod::Fehlberg,
+            Self::DormandPrince => RungeKuttaMethod::DormandPrince,
+        }
+    }
+}
+
+/// Settings of the field line tracer. Distances are in planetary radii, and steps are arc
+/// lengths along the field line. The defaults are
+///
+/// - an initial step of 0.025 and a maximum step of 0.25,
+/// - absolute and relative tolerances of 1e-4,
+/// - the Dormand-Prince method,
+/// - an outer boundary at r = 200,
+/// - an inner boundary on the ellipsoid with an equatorial radius of 1 and a flattening of 1/15.4,
+/// - at most 10^6 steps in each direction, so that a field line that never leaves the domain
+///   (e.g. one that closes on itself) still terminates.
+///
+/// The settings are chained on the default, e.g.
+///
+/// ```
+/// # use iupitermag::trace::TraceConfig;
+/// let config = TraceConfig::default()
+///     .tolerances(1e-6, 1e-6)
+///     .outer_boundary(300.)
+///     .max_steps(100_000);
+/// assert!(config.validate().is_ok());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceConfig {
+    initial_step: f64,
+    max_step: f64,
+    absolute_tolerance: f64,
+    relative_tolerance: f64,
+    method: TraceMethod,
+    outer_boundary: f64,
+    equatorial_radius: f64,
+    flattening: f64,
+    max_steps: usize,
+}
+
+impl Default for TraceConfig {
+    fn default() -> Self {
+        TraceConfig {
+            initial_step: 0.025,
+            max_step: 0.25,
+            absolute_tolerance: 1e-4,
+            relative_tolerance: 1e-4,
+            method: TraceMethod::DormandPrince,
+            outer_boundary: 200.,
+            equatorial_radius: 1.,
+            flattening: 1. / 15.4,
+            max_steps: 1_000_000,
+        }
+    }
+}
+
+impl TraceConfig {
+    /// Set the size of the first step.
+    pub fn initial_step(mut self, initial_step: f64) -> Self {
+        self.initial_step = initial_step;
+        self
+    }
+
+    /// Set the largest step the adaptive integrator may take.
+    pub fn max_step(mut self, max_step: f64) -> Self {
+        self.max_step = max_step;
+        self
+    }
+
+    /// Set the absolute and relative tolerances of the adaptive step size control.
+    pub fn tolerances(mut self, absolute: f64, relative: f64) -> Self {
+        self.absolute_tolerance = absolute;
+        self.relative_tolerance = relative;
+        self
+    }
+
+    /// Get the absolute and relative tolerances of the adaptive step size control.
+    pub fn get_tolerances(&self) -> (f64, f64) {
+        (self.absolute_tolerance, self.relative_tolerance)
+    }
+
+    /// Set the Runge-Kutta method.
+    pub fn method(mut self, method: TraceMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// Set the radius beyond which tracing stops.
+    pub fn outer_boundary(mut self, radius: f64) -> Self {
+        self.outer_boundary = radius;
+        self
+    }
+
+    /// Set the inner boundary to the ellipsoid with the given equatorial radius and flattening,
+    /// (a - c) / a. A flattening of 0 gives a sphere.
+    pub fn inner_boundary(mut self, equatorial_radius: f64, flattening: f64) -> Self {
+        self.equatorial_radius = equatorial_radius;
+        self.flattening = flattening;
+        self
+    }
+
+    /// Get the equatorial radius and flattening of the inner boundary ellipsoid.
+    pub fn get_inner_boundary(&self) -> (f64, f64) {
+        (self.equatorial_radius, self.flattening)
+    }
+
+    /// Limit the number of steps in each direction from the starting point (default 10^6).
+    pub fn max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Check that the steps and tolerances are positive, the max step is not smaller than the
+    /// initial step, the flattening is in [0, 1) and the outer boundary lies outside the inner
+    /// boundary.
+    pub fn validate(&self) -> Result<(), Error> {
+        let positive = [
+            ("initial_step", self.initial_step),
+            ("max_step", self.max_step),
+            ("absolute_tolerance", self.absolute_tolerance),
+            ("relative_tolerance", self.relative_tolerance),
+            ("equatorial_radius", self.equatorial_radius),
+        ];
+        if let Some((name, value)) = positive.iter().find(|(_, v)| !(v.is_finite() && *v > 0.)) {
+            return Err(Error::InvalidTraceConfig(format!(
+                "{name} must be positive and finite, got {value}"
+            )));
+        }
+        if self.max_step < self.initial_step {
+            return Err(Error::InvalidTraceConfig(format!(
+                "max_step ({}) must not be smaller than initial_step ({})",
+                self.max_step, self.initial_step
+            )));
+        }
+        if !(0. ..1.).contains(&self.flattening) {
+            return Err(Error::InvalidTraceConfig(format!(
+                "flattening must be in [0, 1), got {}",
+                self.flattening
+            )));
+        }
+        if self.outer_boundary.is_nan() || self.outer_boundary <= self.equatorial_radius {
+            return Err(Error::InvalidTraceConfig(format!(
+                "outer_boundary ({}) must be larger than the equatorial radius ({})",
+                self.outer_boundary, self.equatorial_radius
+            )));
+        }
+        Ok(())
+    }
+
+    /// Check if a point is inside the inner boundary ellipsoid.
+    fn is_inside_planet(&self, pos: ArrayView1<f64>) -> bool {
+        let a = self.equatorial_radius;
+        let c = (1.0 - self.flattening) * a;
+        let r_ellipsoid_norm =
+            pos[0].powi(2) / a.powi(2) + pos[1].powi(2) / a.powi(2) + pos[2].powi(2) / c.powi(2);
+
+        r_ellipsoid_norm < 1.
+    }
+
+    /// Check if a point is outside the outer boundary.
+    fn is_outside_boundary(&self, pos: ArrayView1<f64>) -> bool {
+        pos.dot(&pos).sqrt() > self.outer_boundary
+    }
+
+    /// Trace the field line through `start_position` in both directions. The config and the
+    /// shape of the starting position are assumed to have been checked.
+    fn trace<F: Field + ?Sized>(
+        &self,
+        field: &F,
+        start_position: &Array1<f64>,
+    ) -> Result<Trace, Error> {
+        Ok(Trace {
+            north: self.trace_direction(field, start_position, -1.)?,
+            south: self.trace_direction(field, start_position, 1.)?,
+        })
+    }
+
+    /// Trace a field line from `start_position` along `direction` (1 or -1 times the field) until
+    /// it leaves the domain, the field vanishes or the maximum number of steps is reached.
+    fn trace_direction<F: Field + ?Sized>(
+        &self,
+        field: &F,
+        start_position: &Array1<f64>,
+        direction: f64,
+    ) -> Result<TraceSegment, Error> {
+        let tolerance = |value| Array1::from_elem(start_position.len(), value);
+
+        // Set when the field vanishes (or is not finite) at a point of the last step, which has no
+        // direction to follow.
+        let null_field = Cell::new(false);
+
+        let integrator = RungeKutta::builder(
+            |_, p, mut val| match calc_b_unit_vector(field, p) {
+                Some(b_unit) => {
+                    for (v, b) in val.iter_mut().zip(b_unit) {
+                        *v = b * direction;
+                    }
+                }
+                None => {
+                    null_field.set(true);
+                    val.fill(0.);
+                }
+            },
+            |_, p| null_field.get() || self.is_inside_planet(p) || self.is_outside_boundary(p),
+        )
+        .initial_condition(0., start_position.clone())
+        .initial_step_size(self.initial_step)
+        .method(self.method.runge_kutta_method(), true) // `true` for adaptive step-size
+        .tolerances(
+            tolerance(self.absolute_tolerance),
+            tolerance(self.relative_tolerance),
+        )
+        .set_max_step_size(self.max_step)
+        .build()?;
+
+        let mut arc_length = vec![0.];
+        let mut positions = start_position.to_vec();
+        for (t, p) in integrator.take(self.max_steps) {
+            arc_length.push(t);
+            positions.extend(p);
+        }
+
+        let positions = Array2::from_shape_vec((arc_length.len(), 3), positions)
+            .expect("positions should have 3 components");
+        let end = positions.row(positions.nrows() - 1);
+        let termination = if self.is_inside_planet(end) {
+            Termination::Planet
+        } else if self.is_outside_boundary(end) {
+            Termination::OuterBoundary
+        } else if null_field.get() {
+            Termination::NullField
+        } else {
+            Termination::MaxSteps
+        };
+
+        let b = field.map_calc_field_xyz(positions.view());
+        let b_magnitude = b.rows().into_iter().map(|x| x.dot(&x).sqrt()).collect();
+
+        Ok(TraceSegment {
+            positions,
+            arc_length: Array1::from_vec(arc_length),
+            b,
+            b_magnitude,
+            termination,
+        })
+    }
 }
 
-/// Calculate the magnetic field unit vector at a point.
-fn calc_b_unit_vector(field: &PlanetField, pos: ArrayView1<f64>) -> Array1<f64> {
-    let b = field.calc_field_xyz(pos[0], pos[1], pos[2]);
-    let b_mag = b.dot(&b).sqrt();
-    b / b_mag
+/// Why the tracing of a field line segment stopped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Termination {
+    /// The segment reached the inner boundary.
+    Planet,
+    /// The segment left the outer boundary.
+    OuterBoundary,
+    /// The maximum number of steps was reached.
+    MaxSteps,
+    /// The field vanished or was not finite, so the field line has no direction.
+    NullField,
 }
 
-/// Calculate the inverse of the magnetic field unit vector.
-fn calc_b_unit_vector_inverse(field: &PlanetField, pos: ArrayView1<f64>) -> Array1<f64> {
-    calc_b_unit_vector(field, pos) * (-1.0)
+/// Classification of a traced field line by where its two ends terminate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Topology {
+    /// Both ends reach the planet.
+    Closed,
+    /// One end reaches the planet and the other leaves the outer boundary.
+    Open,
+    /// Any other combination, e.g. a segment that ran out of steps or hit a null.
+    Unknown,
 }
 
-/// Check if point is inside Jupiter's ellipsoid or outside the bounds of tracing.
-fn is_inside_jupiter(pos: ArrayView1<f64>) -> bool {
-    let f: f64 = 1.0 / 15.4; // Polar flattening of Jupiter ellipsoid.
-    let a: f64 = 1.0;
-    let c: f64 = (1.0 - f) * a;
-    let r_ellipsoid_norm =
-        pos[0].powi(2) / a.powi(2) + pos[1].powi(2) / a.powi(2) + pos[2].powi(2) / c.powi(2);
+/// One half of a traced field line, starting at the starting point of the trace.
+#[derive(Clone, Debug)]
+pub struct TraceSegment {
+    /// Cartesian positions of shape (M, 3), the first row being the starting point.
+    pub positions: Array2<f64>,
+    /// Cumulative arc length from the starting point, of shape (M,).
+    pub arc_length: Array1<f64>,
+    /// Cartesian field (Bx, By, Bz) in nT at each position, of shape (M, 3).
+    pub b: Array2<f64>,
+    /// Field magnitude |B| in nT at each position, of shape (M,).
+    pub b_magnitude: Array1<f64>,
+    /// Why the tracing stopped.
+    pub termination: Termination,
+}
 
-    r_ellipsoid_norm < 1. || pos.dot(&pos).sqrt() > R_TRACE_MAXIMUM
+/// A field line traced in both directions from a starting point. The `north` segment follows
+/// -B and the `south` segment +B, which lead to the northern and southern hemispheres for a
+/// planet whose field points out of the northern hemisphere, such as Jupiter.
+#[derive(Clone, Debug)]
+pub struct Trace {
+    /// Segment traced against the field.
+    pub north: TraceSegment,
+    /// Segment traced along the field.
+    pub south: TraceSegment,
 }
 
-struct PlanetField {
-    internal_field: InternalField,
-    currentsheet_field: CurrentSheetField,
+impl Trace {
+    /// Classify the field line as closed, open or unknown from the termination of its segments.
+    pub fn topology(&self) -> Topology {
+        match (self.north.termination, self.south.termination) {
+            (Termination::Planet, Termination::Planet) => Topology::Closed,
+            (Termination::Planet, Termination::OuterBoundary)
+            | (Termination::OuterBoundary, Termination::Planet) => Topology::Open,
+            _ => Topology::Unknown,
+        }
+    }
+
+    /// Positions along the entire field line, from the end of the north segment through the
+    /// starting point to the end of the south segment, of shape (M, 3).
+    pub fn positions(&self) -> Array2<f64> {
+        concatenate![
+            Axis(0),
+            self.north.positions.slice(s![..;-1, ..]),
+            self.south.positions.slice(s![1.., ..])
+        ]
+    }
+
+    /// Total arc length of the field line.
+    pub fn length(&self) -> f64 {
+        self.north.arc_length[self.north.arc_length.len() - 1]
+            + self.south.arc_length[self.south.arc_length.len() - 1]
+    }
+}
+
+/// Function to trace field lines of `field` from a starting position to the planet,
+/// both along and against the field, using the settings in `config`. Returns the [`Trace`] of
+/// the field line, or an error if the config is invalid, the starting position does not have 3
+/// components or the integrator could not be built. Any [`Field`] can be traced, including a
+/// trait object.
+pub fn trace_field_to_planet<F: Field + ?Sized>(
+    start_position: Array1<f64>,
+    field: &F,
+    config: &TraceConfig,
+) -> Result<Trace, Error> {
+    config.validate()?;
+    if start_position.len() != 3 {
+        return Err(Error::InvalidShape(format!(
+            "the starting position must have 3 components, got {}",
+            start_position.len()
+        )));
+    }
+
+    config.trace(field, &start_position)
 }
 
-impl Field for PlanetField {
-    fn calc_field(&self, r: f64, theta: f64, phi: f64) -> Array1<f64> {
-        let b_internal = self.internal_field.calc_field(r, theta, phi);
-        let b_currentsheet = self.currentsheet_field.calc_field(r, theta, phi);
-        b_internal + b_currentsheet
+/// Trace field lines of `field` from each row of `start_positions` (shape (N, 3)) like
+/// [`trace_field_to_planet`], spreading the starting points across threads with Rayon. Returns
+/// the traces in the order of the starting points.
+pub fn par_trace_field_to_planet<F: Field + Sync + ?Sized>(
+    start_positions: ArrayView2<f64>,
+    field: &F,
+    config: &TraceConfig,
+) -> Result<Vec<Trace>, Error> {
+    config.validate()?;
+    if start_positions.ncols() != 3 {
+        return Err(Error::InvalidShape(format!(
+            "the starting positions must have shape (N, 3), got {:?}",
+            start_positions.shape()
+        )));
     }
+
+    start_positions
+        .axis_iter(Axis(0))
+        .into_par_iter()
+        .map(|start_position| config.trace(field, &start_position.to_owned()))
+        .collect()
+}
+
+/// Calculate the magnetic field unit vector at a point, or `None` if the field vanishes or is
+/// not finite there.
+fn calc_b_unit_vector<F: Field + ?Sized>(field: &F, pos: ArrayView1<f64>) -> Option<[f64; 3]> {
+    let b = field.calc_field_xyz_array(pos[0], pos[1], pos[2]);
+    let b_mag = (b[0] * b[0] + b[1] * b[1] + b[2] * b[2]).sqrt();
+    (b_mag > 0. && b_mag.is_finite()).then(|| b.map(|x| x / b_mag))
 }
 
 #[cfg(test)]
 mod tests {
     #[test]
     fn test_trace_to_planet() {
-        use crate::currentsheet::{CurrentSheetField, IntegrationType};
+        use crate::currentsheet::CurrentSheetField;
+        use crate::currentsheet::IntegrationType;
+        use crate::field::CompositeField;
         use crate::internal::InternalField;
-        use crate::trace::trace_field_to_planet;
+        use crate::trace::{TraceConfig, trace_field_to_planet};
         use ndarray::Array;
 
         let internal_field = InternalField::new("JRM33", None, None, Some(10));
 
         let currentsheet_field =
             CurrentSheetField::new("CON2020".to_string(), None, IntegrationType::Analytic);
+        let field = CompositeField::new()
+            .with(internal_field.clone())
+            .with(currentsheet_field.clone());
 
         let start_position = Array::from_vec(vec![-10.0, 2.0, 3.0]);
-        let val = trace_field_to_planet(start_position, &internal_field, &currentsheet_field);
+        let val = trace_field_to_planet(start_position, &field, &TraceConfig::default())
+            .unwrap()
+            .positions();
 
         let val_test = Array::from_vec(vec![
             -0.5281993369952284,
@@ -140,4 +443,256 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_trace_config() {
+        use crate::currentsheet::CurrentSheetField;
+        use crate::currentsheet::IntegrationType;
+        use crate::field::CompositeField;
+        use crate::internal::InternalField;
+        use crate::trace::{TraceConfig, TraceMethod, trace_field_to_planet};
+        use ndarray::Array;
+
+        let internal_field = InternalField::new("JRM33", None, None, Some(10));
+        let currentsheet_field =
+            CurrentSheetField::new("CON2020".to_string(), None, IntegrationType::Analytic);
+        let field = CompositeField::new()
+            .with(internal_field.clone())
+            .with(currentsheet_field.clone());
+        let start_position = Array::from_vec(vec![-10.0, 2.0, 3.0]);
+        let trace = |config: &TraceConfig| {
+            trace_field_to_planet(start_position.clone(), &field, config).map(|x| x.positions())
+        };
+
+        let config = TraceConfig::default()
+            .tolerances(1e-7, 1e-6)
+            .inner_boundary(1.1, 0.);
+        assert!(
+            config.get_tolerances() == (1e-7, 1e-6) && config.get_inner_boundary() == (1.1, 0.),
+            "Trace Config Test Failed: \n Calculated {:?}",
+            config
+        );
+
+        // Tighter tolerances and another method land on the same footpoint. The last point lies
+        // just inside the planet by up to one step, so compare directions.
+        let val = trace(&TraceConfig::default()).unwrap();
+        let val_tight = trace(
+            &TraceConfig::default()
+                .tolerances(1e-7, 1e-7)
+                .max_step(0.1)
+                .method(TraceMethod::Fehlberg),
+        )
+        .unwrap();
+        let (end, end_tight) = (val.row(0), val_tight.row(0));
+        let cos_angle = end.dot(&end_tight) / (end.dot(&end) * end_tight.dot(&end_tight)).sqrt();
+        assert!(
+            cos_angle > 1. - 1e-6,
+            "Trace Config Test Failed: \n Calculated {:?}, Expected {:?}",
+            end_tight,
+            end
+        );
+
+        // The step limit applies to each direction, and the outer boundary stops the trace.
+        let val_short = trace(&TraceConfig::default().max_steps(5)).unwrap();
+        assert_eq!(val_short.nrows(), 11);
+
+        let val_outer = trace(&TraceConfig::default().outer_boundary(15.)).unwrap();
+        let r_end = val_outer
+            .row(val_outer.nrows() - 1)
+            .dot(&val_outer.row(val_outer.nrows() - 1));
+        assert!(
+            r_end.sqrt() > 15. && val_outer.nrows() < val.nrows(),
+            "Trace Config Test Failed: \n Calculated {:?}",
+            val_outer
+        );
+
+        assert!(trace(&TraceConfig::default().max_step(0.01)).is_err());
+        assert!(trace(&TraceConfig::default().inner_boundary(1., 1.)).is_err());
+    }
+
+    #[test]
+    fn test_trace_segments() {
+        use crate::currentsheet::CurrentSheetField;
+        use crate::currentsheet::IntegrationType;
+        use crate::field::{CompositeField, Field};
+        use crate::internal::InternalField;
+        use crate::trace::{Termination, Topology, TraceConfig, trace_field_to_planet};
+        use ndarray::{Array, Array2};
+
+        let internal_field = InternalField::new("JRM33", None, None, Some(10));
+        let currentsheet_field =
+            CurrentSheetField::new("CON2020".to_string(), None, IntegrationType::Analytic);
+        let field = CompositeField::new()
+            .with(internal_field.clone())
+            .with(currentsheet_field.clone());
+
+        let val = trace_field_to_planet(
+            Array::from_vec(vec![-10.0, 2.0, 3.0]),
+            &field,
+            &TraceConfig::default(),
+        )
+        .unwrap();
+
+        assert_eq!(val.north.termination, Termination::Planet);
+        assert_eq!(val.south.termination, Termination::Planet);
+        assert_eq!(val.topology(), Topology::Closed);
+        assert!(val.north.positions[[val.north.positions.nrows() - 1, 2]] > 0.);
+        assert!(val.south.positions[[val.south.positions.nrows() - 1, 2]] < 0.);
+
+        // The arc length grows by the distance between points, up to the curvature of the line.
+        for segment in [&val.north, &val.south] {
+            for i in 1..segment.positions.nrows() {
+                let d = &segment.positions.row(i) - &segment.positions.row(i - 1);
+                let ds = segment.arc_length[i] - segment.arc_length[i - 1];
+                assert!(
+                    ds >= d.dot(&d).sqrt() - 1e-9 && ds < 1.01 * d.dot(&d).sqrt() + 1e-9,
+                    "Trace Arc Length Test Failed: \n Calculated {:?}, Expected {:?}",
+                    ds,
+                    d.dot(&d).sqrt()
+                );
+            }
+
+            // The field is sampled at each point.
+            let end = segment.positions.row(segment.positions.nrows() - 1);
+            let b_end = internal_field.calc_field_xyz(end[0], end[1], end[2])
+                + currentsheet_field.calc_field_xyz(end[0], end[1], end[2]);
+            let b_end_test = segment.b.row(segment.b.nrows() - 1);
+            assert!(
+                (&b_end - &b_end_test).iter().all(|x| x.abs() < 1e-6)
+                    && (segment.b_magnitude[segment.b_magnitude.len() - 1]
+                        - b_end.dot(&b_end).sqrt())
+                    .abs()
+                        < 1e-6,
+                "Trace Field Test Failed: \n Calculated {:?}, Expected {:?}",
+                b_end_test,
+                b_end
+            );
+        }
+
+        // A line leaving the outer boundary in one direction is open, and a trace that runs out
+        // of steps is unknown.
+        let val_open = trace_field_to_planet(
+            Array::from_vec(vec![-60.0, 0.0, 2.0]),
+            &field,
+            &TraceConfig::default().outer_boundary(61.),
+        )
+        .unwrap();
+        assert_eq!(val_open.topology(), Topology::Open);
+
+        let val_short = trace_field_to_planet(
+            Array::from_vec(vec![-10.0, 2.0, 3.0]),
+            &field,
+            &TraceConfig::default().max_steps(5),
+        )
+        .unwrap();
+        assert_eq!(val_short.north.termination, Termination::MaxSteps);
+        assert_eq!(val_short.topology(), Topology::Unknown);
+
+        // Tracing in a field that vanishes stops immediately.
+        let null_field = InternalField::new(
+            "Custom",
+            Some(Array2::zeros((2, 2))),
+            Some(Array2::zeros((2, 2))),
+            None,
+        );
+        let val_null = trace_field_to_planet(
+            Array::from_vec(vec![-10.0, 2.0, 3.0]),
+            &null_field,
+            &TraceConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(val_null.north.termination, Termination::NullField);
+        assert_eq!(val_null.south.termination, Termination::NullField);
+        assert!(val_null.positions().nrows() <= 3 && val_null.length() < 0.1);
+    }
+
+    #[test]
+    fn test_trace_dipole() {
+        use crate::field::Field;
+        use crate::internal::InternalField;
+        use crate::trace::{Topology, TraceConfig, trace_field_to_planet};
+        use ndarray::{Array, Array2};
+
+        let mut g = Array2::<f64>::zeros((2, 2));
+        g[[1, 0]] = 410993.4;
+        let dipole = InternalField::new("Custom", Some(g), Some(Array2::zeros((2, 2))), None);
+
+        // Trace an internal field alone, through a trait object, to a spherical planet.
+        let field: &dyn Field = &dipole;
+        let val = trace_field_to_planet(
+            Array::from_vec(vec![5.0, 0.0, 0.0]),
+            field,
+            &TraceConfig::default().inner_boundary(1., 0.),
+        )
+        .unwrap();
+        assert_eq!(val.topology(), Topology::Closed);
+
+        // Dipole field lines follow r = L sin^2(theta).
+        for pos in val.positions().rows() {
+            let r = pos.dot(&pos).sqrt();
+            let sin2_theta = (pos[0].powi(2) + pos[1].powi(2)) / r.powi(2);
+            assert!(
+                (r / sin2_theta - 5.).abs() < 1e-3,
+                "Trace Dipole Test Failed: \n Calculated {:?}, Expected {:?}",
+                r / sin2_theta,
+                5.
+            );
+        }
+    }
+
+    #[test]
+    fn test_par_trace_field_to_planet() {
+        use crate::currentsheet::{CurrentSheetField, IntegrationType};
+        use crate::error::Error;
+        use crate::field::CompositeField;
+        use crate::internal::InternalField;
+        use crate::trace::{TraceConfig, par_trace_field_to_planet, trace_field_to_planet};
+        use ndarray::array;
+
+        let field = CompositeField::new()
+            .with(InternalField::new("JRM33", None, None, Some(10)))
+            .with(CurrentSheetField::new(
+                "CON2020".to_string(),
+                None,
+                IntegrationType::Analytic,
+            ));
+        let start_positions = array![[-10.0, 2.0, 3.0], [15., 0., 0.], [-8., -4., -1.]];
+        let config = TraceConfig::default();
+
+        let val = par_trace_field_to_planet(start_positions.view(), &field, &config).unwrap();
+        assert_eq!(val.len(), 3);
+
+        for (trace, start_position) in val.iter().zip(start_positions.rows()) {
+            let val_test = trace_field_to_planet(start_position.to_owned(), &field, &config)
+                .unwrap()
+                .positions();
+            assert!(
+                trace.positions() == val_test,
+                "Parallel Trace Test Failed: \n Calculated {:?}, Expected {:?}",
+                trace.positions(),
+                val_test
+            );
+        }
+
+        assert!(
+            par_trace_field_to_planet(
+                start_positions.view(),
+                &field,
+                &config.clone().max_step(0.01)
+            )
+            .is_err()
+        );
+
+        // Starting positions without 3 components are rejected instead of panicking.
+        let val = par_trace_field_to_planet(array![[-10., 2.], [15., 0.]].view(), &field, &config);
+        assert!(
+            matches!(val, Err(Error::InvalidShape(_))),
+            "Parallel Trace Test Failed"
+        );
+        let val = trace_field_to_planet(array![-10., 2.], &field, &config);
+        assert!(
+            matches!(val, Err(Error::InvalidShape(_))),
+            "Parallel Trace Test Failed"
+        );
+    }
 }
@@ -75,7 +75,27 @@ def bench_for_num(N, g, h, degree):
     return (med1, med2, med3, med4)
 
 
+def bench_grid(n_theta=180, n_phi=360):
+    thetas = np.linspace(0.0, np.pi, n_theta)
+    phis = np.linspace(0.0, 2.0 * np.pi, n_phi)
+    theta_grid, phi_grid = np.meshgrid(thetas, phis, indexing="ij")
+    positions = np.column_stack(
+        (np.ones(theta_grid.size), theta_grid.ravel(), phi_grid.ravel())
+    )
+
+    field = im.InternalField("JRM33")
+
+    med_grid = bench("calc_field_grid", lambda: field.calc_field_grid(1.0, thetas, phis), 0)
+    med_parmap = bench("parmap_calc_field", lambda: field.parmap_calc_field(positions), 0)
+
+    print(f"Benchmark: InternalField('JRM33') on a {n_theta} x {n_phi} grid")
+    print(f"calc_field_grid:   {med_grid * 1e3:.1f} ms")
+    print(f"parmap_calc_field: {med_parmap * 1e3:.1f} ms ({med_parmap / med_grid:.0f}x)")
+
+
 if __name__ == "__main__":
+    bench_grid()
+
     g, h, degree = read_coefficients_file(JRM33_COEFFICIENTS_FILE, degree=10)
 
     len_num = 1000
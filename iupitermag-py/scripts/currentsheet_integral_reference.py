@@ -0,0 +1,94 @@
+"""
+Reference values for the numerical integral form of the CON2020 current sheet, used in
+`test_calc_currentsheet_field_integral` in iupitermag-core/src/currentsheet.rs.
+
+The field of a single semi-infinite disc of inner radius `a` is evaluated with mpmath,
+independently of the quadrature in iupitermag:
+
+- Bz from the equivalent elliptic integral form. Using
+  int_0^inf exp(-s lambda) J0(lambda rho) J0(lambda a) dlambda = (2 / pi) K(m) / sqrt(s^2 + (rho + a)^2)
+  with m = 4 rho a / (s^2 + (rho + a)^2), the lambda integrals of the Bz integrand become finite
+  integrals over s.
+- Brho from the Bessel integral itself, which decays exponentially away from |z| = d, split
+  into intervals shorter than the oscillations of the integrand.
+
+Usage: python currentsheet_integral_reference.py
+"""
+
+import mpmath as mp
+
+mp.mp.dps = 20
+
+MU0_I_2 = 139.6
+D = 3.6
+
+
+def laplace_j0_j0(s, rho, a):
+    # K(m) = pi / (2 agm(1, sqrt(1 - m))), with 1 - m computed directly so that it does not
+    # round to zero close to the logarithmic singularity at s = 0 when rho = a.
+    outer = s * s + (rho + a) ** 2
+    m1 = (s * s + (rho - a) ** 2) / outer
+    return 1 / (mp.agm(1, mp.sqrt(m1)) * mp.sqrt(outer))
+
+
+def b_z(rho, z, a):
+    rho, z, a = mp.mpf(rho), mp.mpf(z), mp.mpf(a)
+    abs_z = abs(z)
+    if abs_z < D:
+        # Split off the integrable logarithmic singularity of K at s = 0 when rho = a.
+        return MU0_I_2 * (
+            mp.quad(lambda s: laplace_j0_j0(s, rho, a), [0, 1e-6, D - z])
+            + mp.quad(lambda s: laplace_j0_j0(s, rho, a), [0, 1e-6, D + z])
+        )
+    return MU0_I_2 * mp.quad(lambda s: laplace_j0_j0(s, rho, a), [abs_z - D, abs_z + D])
+
+
+def b_rho(rho, z, a):
+    rho, z, a = mp.mpf(rho), mp.mpf(z), mp.mpf(a)
+    if rho == 0:
+        return mp.mpf(0)
+
+    abs_z = abs(z)
+    if abs_z < D:
+        decay = D - abs_z
+        sign = 1
+
+        def integrand(lam):
+            return (
+                mp.besselj(1, lam * rho) * mp.besselj(0, lam * a) * mp.sinh(lam * z) * mp.exp(-lam * D) / lam
+            )
+
+    else:
+        decay = abs_z - D
+        sign = mp.sign(z)
+
+        def integrand(lam):
+            return (
+                mp.besselj(1, lam * rho) * mp.besselj(0, lam * a) * mp.sinh(lam * D) * mp.exp(-lam * abs_z) / lam
+            )
+
+    lambda_max = 45 / decay
+    h = mp.pi / (rho + a) / 2
+    points = [i * h for i in range(int(lambda_max / h) + 2)]
+    return 2 * MU0_I_2 * sign * mp.quad(integrand, points)
+
+
+if __name__ == "__main__":
+    points = [
+        (10.0, 5.0, 7.8),
+        (8.0, 1.0, 7.8),
+        (7.8, -2.0, 7.8),
+        (7.8, 0.0, 7.8),
+        (20.0, -6.0, 7.8),
+        (5.0, 0.5, 7.8),
+        (30.0, 4.0, 51.4),
+        (60.0, -1.5, 51.4),
+        (7.0, 3.0, 7.8),
+        # Close to the axis, where only J0(lambda a) oscillates at the upper integration limit.
+        (0.0, 1.0, 7.8),
+        (0.2, -2.0, 7.8),
+        (0.5, 3.0, 51.4),
+    ]
+    print("(rho, z, a, Brho, Bz)")
+    for rho, z, a in points:
+        print(f"({rho}, {z}, {a}, {mp.nstr(b_rho(rho, z, a), 15)}, {mp.nstr(b_z(rho, z, a), 15)}),")
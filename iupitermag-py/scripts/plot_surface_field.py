@@ -6,39 +6,30 @@ import iupitermag as im
 plt.style.use("dark_background")
 
 
-def calc_field_iupitermag(pos):
+def calc_field_iupitermag(r, theta, phi):
     internalField = im.InternalField("JRM33")
-    return internalField.map_calc_field(pos)
+    return internalField.calc_field_grid(r, theta, phi)
 
 
 # -----------------------------------------------------------------------------
 if __name__ == "__main__":
     f = 1 / 15.4
     a = 1.0
-    b = a
     c = (1 - f) * a
 
     theta_arr = np.linspace(0, np.pi, 100)
     phi_arr = np.linspace(0, 2 * np.pi, 100)
 
-    theta, phi = np.meshgrid(theta_arr, phi_arr, indexing="ij")
+    # The oblate surface is axisymmetric, so the radius and planetocentric
+    # co-latitude only depend on the theta row.
+    rho = a * np.sin(theta_arr)
+    z = c * np.cos(theta_arr)
+    r = np.sqrt(rho**2 + z**2)
+    theta = np.arccos(z / r)
 
-    theta = np.ravel(theta)
-    phi = np.ravel(phi)
+    mag_array = calc_field_iupitermag(r, theta, phi_arr)
 
-    pos_xyz = np.zeros((100 * 100, 3))
-    pos_xyz[:, 0] = a * np.sin(theta) * np.cos(phi)
-    pos_xyz[:, 1] = b * np.sin(theta) * np.sin(phi)
-    pos_xyz[:, 2] = c * np.cos(theta)
-
-    pos = np.zeros((100 * 100, 3))
-    pos[:, 0] = np.sqrt(np.sum(pos_xyz**2, axis=1))
-    pos[:, 1] = np.arccos(pos_xyz[:, 2] / pos[:, 0])
-    pos[:, 2] = np.arctan2(pos_xyz[:, 1], pos_xyz[:, 0])
-
-    mag_array = calc_field_iupitermag(pos)
-
-    Bmag = np.sqrt(np.sum(mag_array**2, axis=1)).reshape((100, 100))
+    Bmag = np.sqrt(np.sum(mag_array**2, axis=2))
 
     fig = plt.figure(dpi=200, figsize=(6, 3))
     ax = plt.subplot(111, projection="mollweide")
@@ -49,7 +49,7 @@ if __name__ == "__main__":
 
     fig, ax = plt.subplots(1, 1, figsize=(6, 3), dpi=200)
     for t in trace:
-        ax.plot(t[:, 0], t[:, 2], lw=1.0)
+        ax.plot(t["positions"][:, 0], t["positions"][:, 2], lw=1.0)
     ax.axhline(0.0, color="0.5", lw=0.25)
     ax.axvline(0.0, color="0.5", lw=0.25)
     plot_jupiter(ax, color="0.8", zorder=3)
@@ -1,12 +1,13 @@
-use iupitermag::currentsheet::{CurrentSheetField, IntegrationType};
-use numpy::{IntoPyArray, PyArray1, PyArray2, PyReadonlyArray2};
+use iupitermag::currentsheet::{CurrentSheetField, HybridCriterion, IntegrationType};
+use numpy::{IntoPyArray, PyArray1, PyArray2, PyArray3, PyReadonlyArray2};
 use pyo3::{
-    prelude::PyAnyMethods, pyclass, pymethods, types::IntoPyDict, types::PyDict, Bound, PyAny,
-    PyResult, Python,
+    exceptions::PyValueError, prelude::PyAnyMethods, pyclass, pymethods, types::IntoPyDict,
+    types::PyDict, Bound, PyAny, PyResult, Python,
 };
 use std::collections::HashMap;
 
 use crate::impl_field_methods;
+use crate::to_pyerr;
 use iupitermag::field::Field;
 
 #[pyclass]
@@ -17,18 +18,28 @@ pub struct PyCurrentSheetField {
 #[pymethods]
 impl PyCurrentSheetField {
     #[new]
-    pub fn __init__(field_type: String, pyparams: Bound<'_, PyAny>, integration: String) -> Self {
-        let params: HashMap<String, f64> = pyparams.extract().unwrap();
+    pub fn __init__(
+        field_type: String,
+        pyparams: Bound<'_, PyAny>,
+        integration: String,
+    ) -> PyResult<Self> {
+        let params: HashMap<String, f64> = pyparams.extract()?;
 
         let integration_type = match integration.to_lowercase().as_str() {
             "analytic" => IntegrationType::Analytic,
             "integral" => IntegrationType::Integral,
-            _ => panic!("Unrecognized integration type. Allowed - analytic, integral ."),
+            "hybrid" => IntegrationType::Hybrid(HybridCriterion::default()),
+            _ => {
+                return Err(PyValueError::new_err(
+                    "Unrecognized integration type. Allowed - analytic, integral, hybrid .",
+                ));
+            }
         };
 
-        PyCurrentSheetField {
-            field: CurrentSheetField::new(field_type, Some(params), integration_type),
-        }
+        Ok(PyCurrentSheetField {
+            field: CurrentSheetField::try_new(field_type, Some(params), integration_type)
+                .map_err(to_pyerr)?,
+        })
     }
 }
 
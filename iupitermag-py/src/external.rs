@@ -0,0 +1,50 @@
+use crate::impl_field_methods;
+use crate::to_pyerr;
+use iupitermag::external::ExternalHarmonicField;
+use iupitermag::field::Field;
+use numpy::{IntoPyArray, PyArray1, PyArray2, PyArray3, PyArrayMethods, PyReadonlyArray2};
+use pyo3::{pyclass, pymethods, Bound, PyResult, Python};
+use std::path::PathBuf;
+
+/// A pair of (q, s) coefficient arrays returned to Python.
+type PyCoefficients<'py> = (Bound<'py, PyArray2<f64>>, Bound<'py, PyArray2<f64>>);
+
+#[pyclass]
+pub struct PyExternalHarmonicField {
+    pub field: ExternalHarmonicField,
+}
+
+#[pymethods]
+impl PyExternalHarmonicField {
+    #[new]
+    pub fn __init__(
+        q: PyReadonlyArray2<f64>,
+        s: PyReadonlyArray2<f64>,
+        degree: Option<usize>,
+    ) -> PyResult<Self> {
+        Ok(PyExternalHarmonicField {
+            field: ExternalHarmonicField::try_new(q.to_owned_array(), s.to_owned_array(), degree)
+                .map_err(to_pyerr)?,
+        })
+    }
+
+    /// Load a model from a text or JSON coefficient file.
+    #[staticmethod]
+    pub fn from_file(path: PathBuf) -> PyResult<Self> {
+        Ok(PyExternalHarmonicField {
+            field: ExternalHarmonicField::from_file(path).map_err(to_pyerr)?,
+        })
+    }
+
+    /// Write the model to a text or JSON coefficient file.
+    pub fn to_file(&self, path: PathBuf) -> PyResult<()> {
+        self.field.to_file(path).map_err(to_pyerr)
+    }
+
+    pub fn get_coefficients<'py>(&self, py: Python<'py>) -> PyCoefficients<'py> {
+        let (q, s) = self.field.get_coefficients();
+        (q.into_pyarray(py), s.into_pyarray(py))
+    }
+}
+
+impl_field_methods!(PyExternalHarmonicField);
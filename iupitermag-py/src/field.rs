@@ -1,3 +1,11 @@
+use crate::currentsheet::PyCurrentSheetField;
+use crate::external::PyExternalHarmonicField;
+use crate::internal::PyInternalField;
+use iupitermag::field::{CompositeField, Field};
+use numpy::{IntoPyArray, PyArray1, PyArray2, PyArray3, PyReadonlyArray2};
+use pyo3::exceptions::PyTypeError;
+use pyo3::{prelude::PyAnyMethods, pyclass, pymethods, Bound, PyAny, PyRef, PyResult, Python};
+
 // The macro below implements pymethods for various pyclasses throughout the
 // module. This is the only way that I know of to implement shared
 // functionality across pyclasses. Each class contains a field of a different
@@ -32,6 +40,17 @@ macro_rules! impl_field_methods {
                 self.field.calc_field_xyz(x, y, z).into_pyarray(py)
             }
 
+            /// Calculate the gradient tensor dB_i / dx_j at a point in cartesian coordinates.
+            pub fn calc_jacobian_xyz<'py>(
+                &self,
+                py: Python<'py>,
+                x: f64,
+                y: f64,
+                z: f64,
+            ) -> Bound<'py, PyArray2<f64>> {
+                self.field.calc_jacobian_xyz(x, y, z).into_pyarray(py)
+            }
+
             /// Serial iterator into an array of positions to calculate field.
             pub fn map_calc_field<'py>(
                 &self,
@@ -75,6 +94,61 @@ macro_rules! impl_field_methods {
                     .parmap_calc_field_xyz(positions.as_array())
                     .into_pyarray(py)
             }
+
+            /// Serial iterator into an array of positions to calculate the gradient tensor.
+            pub fn map_calc_jacobian_xyz<'py>(
+                &self,
+                py: Python<'py>,
+                positions: PyReadonlyArray2<f64>,
+            ) -> Bound<'py, PyArray3<f64>> {
+                self.field
+                    .map_calc_jacobian_xyz(positions.as_array())
+                    .into_pyarray(py)
+            }
+
+            /// Rayon iterator into an array of positions to calculate the gradient tensor.
+            pub fn parmap_calc_jacobian_xyz<'py>(
+                &self,
+                py: Python<'py>,
+                positions: PyReadonlyArray2<f64>,
+            ) -> Bound<'py, PyArray3<f64>> {
+                self.field
+                    .parmap_calc_jacobian_xyz(positions.as_array())
+                    .into_pyarray(py)
+            }
         }
     };
 }
+
+#[pyclass]
+pub struct PyCompositeField {
+    pub field: CompositeField,
+}
+
+#[pymethods]
+impl PyCompositeField {
+    /// Sum a list of (field, scale) pairs. The fields are copied, which is cheap for the
+    /// coefficient arrays since they are shared.
+    #[new]
+    pub fn __init__(sources: Vec<(Bound<'_, PyAny>, f64)>) -> PyResult<Self> {
+        let mut field = CompositeField::new();
+
+        for (source, scale) in sources {
+            if let Ok(source) = source.extract::<PyRef<PyInternalField>>() {
+                field.push(Box::new(source.field.clone()), scale);
+            } else if let Ok(source) = source.extract::<PyRef<PyCurrentSheetField>>() {
+                field.push(Box::new(source.field.clone()), scale);
+            } else if let Ok(source) = source.extract::<PyRef<PyExternalHarmonicField>>() {
+                field.push(Box::new(source.field.clone()), scale);
+            } else {
+                return Err(PyTypeError::new_err(
+                    "Unsupported field type. Allowed - InternalField, CurrentSheetField, ExternalHarmonicField .",
+                ));
+            }
+        }
+
+        Ok(PyCompositeField { field })
+    }
+}
+
+impl_field_methods!(PyCompositeField);
@@ -0,0 +1,58 @@
+use crate::currentsheet::PyCurrentSheetField;
+use crate::internal::PyInternalField;
+use crate::to_pyerr;
+use iupitermag::fit::CurrentSheetFit;
+use numpy::{IntoPyArray, PyReadonlyArray2};
+use pyo3::{
+    pyfunction,
+    types::{PyDict, PyDictMethods},
+    Bound, PyResult, Python,
+};
+use std::collections::HashMap;
+
+/// Fit the parameters of a current sheet to observations, starting from the parameters of
+/// `initial` and keeping `internal_field` fixed. Returns a dict with the fitted field, the
+/// parameter covariance and the residuals.
+#[allow(clippy::too_many_arguments)]
+#[pyfunction]
+pub fn fit_currentsheet<'py>(
+    py: Python<'py>,
+    positions: PyReadonlyArray2<f64>,
+    observed: PyReadonlyArray2<f64>,
+    internal_field: Bound<'py, PyInternalField>,
+    initial: Bound<'py, PyCurrentSheetField>,
+    bounds: HashMap<String, (f64, f64)>,
+    frozen: Vec<String>,
+    max_iterations: usize,
+) -> PyResult<Bound<'py, PyDict>> {
+    let internal_field = internal_field.borrow();
+
+    let mut problem = CurrentSheetFit::new(&internal_field.field, *initial.borrow().field.params())
+        .max_iterations(max_iterations);
+    for (name, (lower, upper)) in &bounds {
+        problem = problem.bounds(name, *lower, *upper);
+    }
+    for name in &frozen {
+        problem = problem.freeze(name);
+    }
+
+    let result = problem
+        .solve(positions.as_array(), observed.as_array())
+        .map_err(to_pyerr)?;
+
+    let params = result.field.params().to_map();
+    let field = PyCurrentSheetField {
+        field: result.field,
+    };
+
+    let dict = PyDict::new(py);
+    dict.set_item("field", field)?;
+    dict.set_item("params", params)?;
+    dict.set_item("param_names", result.param_names)?;
+    dict.set_item("covariance", result.covariance.into_pyarray(py))?;
+    dict.set_item("residuals", result.residuals.into_pyarray(py))?;
+    dict.set_item("rms", result.rms)?;
+    dict.set_item("iterations", result.iterations)?;
+    dict.set_item("converged", result.converged)?;
+    Ok(dict)
+}
@@ -1,9 +1,22 @@
 use crate::impl_field_methods;
+use crate::models::model_info_to_dict;
+use crate::to_pyerr;
 use iupitermag::field::Field;
 use iupitermag::internal::InternalField;
-use numpy::ndarray::Array2;
-use numpy::{IntoPyArray, PyArray1, PyArray2, PyArrayMethods, PyReadonlyArray2};
-use pyo3::{pyclass, pymethods, Bound, Python};
+use iupitermag::time::Time;
+use numpy::ndarray::{Array1, Array2};
+use numpy::{
+    IntoPyArray, PyArray1, PyArray2, PyArray3, PyArrayMethods, PyReadonlyArray1, PyReadonlyArray2,
+};
+use pyo3::{
+    pyclass, pymethods,
+    types::{PyDict, PyDictMethods},
+    Bound, PyResult, Python,
+};
+use std::path::PathBuf;
+
+/// A pair of (g, h) coefficient arrays returned to Python.
+type PyCoefficients<'py> = (Bound<'py, PyArray2<f64>>, Bound<'py, PyArray2<f64>>);
 
 // #[derive(Clone)]
 #[pyclass]
@@ -19,22 +32,317 @@ impl PyInternalField {
         g_in: Option<PyReadonlyArray2<f64>>,
         h_in: Option<PyReadonlyArray2<f64>>,
         degree_in: Option<usize>,
-    ) -> Self {
+    ) -> PyResult<Self> {
         let g: Option<Array2<f64>> = g_in.map(|x| x.to_owned_array());
         let h: Option<Array2<f64>> = h_in.map(|x| x.to_owned_array());
 
+        Ok(PyInternalField {
+            field: InternalField::try_new(field_type, g, h, degree_in).map_err(to_pyerr)?,
+        })
+    }
+
+    /// Load a model from a text or JSON coefficient file.
+    #[staticmethod]
+    pub fn from_file(path: PathBuf) -> PyResult<Self> {
+        Ok(PyInternalField {
+            field: InternalField::from_file(path).map_err(to_pyerr)?,
+        })
+    }
+
+    /// Write the model to a text or JSON coefficient file.
+    pub fn to_file(&self, path: PathBuf) -> PyResult<()> {
+        self.field.to_file(path).map_err(to_pyerr)
+    }
+
+    /// Return a copy of the model with secular variation, with `epoch` in decimal years.
+    pub fn with_secular_variation(
+        &self,
+        dg: PyReadonlyArray2<f64>,
+        dh: PyReadonlyArray2<f64>,
+        epoch: f64,
+    ) -> PyResult<Self> {
+        Ok(PyInternalField {
+            field: self
+                .field
+                .clone()
+                .with_secular_variation(
+                    dg.to_owned_array(),
+                    dh.to_owned_array(),
+                    Time::DecimalYear(epoch),
+                )
+                .map_err(to_pyerr)?,
+        })
+    }
+
+    /// Epoch of the model in decimal years, if it has secular variation.
+    pub fn epoch(&self) -> Option<f64> {
+        self.field.epoch()
+    }
+
+    pub fn get_secular_variation<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> Option<PyCoefficients<'py>> {
+        self.field
+            .get_secular_variation()
+            .map(|(dg, dh)| (dg.into_pyarray(py), dh.into_pyarray(py)))
+    }
+
+    /// Return the model evolved to `time` in decimal years.
+    pub fn at_time(&self, time: f64) -> Self {
         PyInternalField {
-            field: InternalField::new(field_type, g, h, degree_in),
+            field: self.field.at_time(Time::DecimalYear(time)),
         }
     }
 
+    /// Calculate the field at a point at `time` in decimal years.
+    pub fn calc_field_at_time<'py>(
+        &self,
+        py: Python<'py>,
+        r: f64,
+        theta: f64,
+        phi: f64,
+        time: f64,
+    ) -> Bound<'py, PyArray1<f64>> {
+        Array1::from_vec(
+            self.field
+                .calc_field_at_time(r, theta, phi, Time::DecimalYear(time))
+                .to_vec(),
+        )
+        .into_pyarray(py)
+    }
+
+    /// Serial iterator into an array of positions and times to calculate the field.
+    pub fn map_calc_field_at_time<'py>(
+        &self,
+        py: Python<'py>,
+        positions: PyReadonlyArray2<f64>,
+        times: PyReadonlyArray1<f64>,
+    ) -> PyResult<Bound<'py, PyArray2<f64>>> {
+        self.field
+            .map_calc_field_at_time(positions.as_array(), times.as_array())
+            .map(|b| b.into_pyarray(py))
+            .map_err(to_pyerr)
+    }
+
+    /// Rayon iterator into an array of positions and times to calculate the field.
+    pub fn parmap_calc_field_at_time<'py>(
+        &self,
+        py: Python<'py>,
+        positions: PyReadonlyArray2<f64>,
+        times: PyReadonlyArray1<f64>,
+    ) -> PyResult<Bound<'py, PyArray2<f64>>> {
+        self.field
+            .parmap_calc_field_at_time(positions.as_array(), times.as_array())
+            .map(|b| b.into_pyarray(py))
+            .map_err(to_pyerr)
+    }
+
+    /// Calculate the magnetic scalar potential at a point, in nT planetary radii.
+    pub fn calc_potential(&self, r: f64, theta: f64, phi: f64) -> f64 {
+        self.field.calc_potential(r, theta, phi)
+    }
+
+    /// Serial iterator into an array of positions to calculate the scalar potential.
+    pub fn map_calc_potential<'py>(
+        &self,
+        py: Python<'py>,
+        positions: PyReadonlyArray2<f64>,
+    ) -> Bound<'py, PyArray1<f64>> {
+        self.field
+            .map_calc_potential(positions.as_array())
+            .into_pyarray(py)
+    }
+
+    /// Rayon iterator into an array of positions to calculate the scalar potential.
+    pub fn parmap_calc_potential<'py>(
+        &self,
+        py: Python<'py>,
+        positions: PyReadonlyArray2<f64>,
+    ) -> Bound<'py, PyArray1<f64>> {
+        self.field
+            .parmap_calc_potential(positions.as_array())
+            .into_pyarray(py)
+    }
+
+    /// Return a copy of the model with a coefficient covariance matrix in nT^2.
+    pub fn with_covariance(&self, covariance: PyReadonlyArray2<f64>) -> PyResult<Self> {
+        Ok(PyInternalField {
+            field: self
+                .field
+                .clone()
+                .with_covariance(covariance.to_owned_array())
+                .map_err(to_pyerr)?,
+        })
+    }
+
+    /// Return a copy of the model with uncorrelated coefficient uncertainties in nT.
+    pub fn with_standard_deviations(
+        &self,
+        sigma_g: PyReadonlyArray2<f64>,
+        sigma_h: PyReadonlyArray2<f64>,
+    ) -> PyResult<Self> {
+        Ok(PyInternalField {
+            field: self
+                .field
+                .clone()
+                .with_standard_deviations(sigma_g.to_owned_array(), sigma_h.to_owned_array())
+                .map_err(to_pyerr)?,
+        })
+    }
+
+    /// Get the coefficient covariance matrix, if any.
+    pub fn covariance<'py>(&self, py: Python<'py>) -> Option<Bound<'py, PyArray2<f64>>> {
+        self.field.covariance().map(|x| x.into_pyarray(py))
+    }
+
+    /// Derivatives of (Br, Btheta, Bphi) at a point with respect to each coefficient.
+    pub fn design_matrix_rows<'py>(
+        &self,
+        py: Python<'py>,
+        r: f64,
+        theta: f64,
+        phi: f64,
+    ) -> Bound<'py, PyArray2<f64>> {
+        self.field
+            .design_matrix_rows(r, theta, phi)
+            .into_pyarray(py)
+    }
+
+    /// Calculate the 1-sigma uncertainty of the field at a point.
+    pub fn calc_field_uncertainty<'py>(
+        &self,
+        py: Python<'py>,
+        r: f64,
+        theta: f64,
+        phi: f64,
+    ) -> PyResult<Bound<'py, PyArray1<f64>>> {
+        let val = self
+            .field
+            .calc_field_uncertainty(r, theta, phi)
+            .map_err(to_pyerr)?;
+        Ok(Array1::from_vec(val.to_vec()).into_pyarray(py))
+    }
+
+    /// Serial iterator into an array of positions to calculate the field uncertainty.
+    pub fn map_calc_field_uncertainty<'py>(
+        &self,
+        py: Python<'py>,
+        positions: PyReadonlyArray2<f64>,
+    ) -> PyResult<Bound<'py, PyArray2<f64>>> {
+        Ok(self
+            .field
+            .map_calc_field_uncertainty(positions.as_array())
+            .map_err(to_pyerr)?
+            .into_pyarray(py))
+    }
+
+    /// Rayon iterator into an array of positions to calculate the field uncertainty.
+    pub fn parmap_calc_field_uncertainty<'py>(
+        &self,
+        py: Python<'py>,
+        positions: PyReadonlyArray2<f64>,
+    ) -> PyResult<Bound<'py, PyArray2<f64>>> {
+        Ok(self
+            .field
+            .parmap_calc_field_uncertainty(positions.as_array())
+            .map_err(to_pyerr)?
+            .into_pyarray(py))
+    }
+
     pub fn get_coefficients<'py>(
         &self,
         py: Python<'py>,
-    ) -> (Bound<'py, PyArray2<f64>>, Bound<'py, PyArray2<f64>>) {
+    ) -> PyCoefficients<'py> {
         let (g, h) = self.field.get_coefficients();
         (g.into_pyarray(py), h.into_pyarray(py))
     }
+
+    /// Get the dipole properties of the model as a dict.
+    pub fn dipole<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dipole = self.field.dipole().map_err(to_pyerr)?;
+
+        let dict = PyDict::new(py);
+        dict.set_item("b0", dipole.b0)?;
+        dict.set_item("moment", dipole.moment)?;
+        dict.set_item("tilt", dipole.tilt)?;
+        dict.set_item("pole_longitude", dipole.pole_longitude)?;
+        dict.set_item("offset", dipole.offset.to_vec())?;
+        Ok(dict)
+    }
+
+    /// Return the model expressed in a frame rotated by the (3, 3) matrix `rotation`.
+    pub fn rotate(&self, rotation: PyReadonlyArray2<f64>) -> PyResult<Self> {
+        Ok(PyInternalField {
+            field: self.field.rotate(rotation.as_array()).map_err(to_pyerr)?,
+        })
+    }
+
+    /// Return the model expressed in the MAG frame of its own dipole.
+    pub fn in_dipole_frame(&self) -> PyResult<Self> {
+        Ok(PyInternalField {
+            field: self.field.in_dipole_frame().map_err(to_pyerr)?,
+        })
+    }
+
+    /// Lowes-Mauersberger power spectrum R_n in nT^2 at radius `r`, indexed by degree.
+    pub fn power_spectrum<'py>(&self, py: Python<'py>, r: f64) -> Bound<'py, PyArray1<f64>> {
+        self.field.power_spectrum(r).into_pyarray(py)
+    }
+
+    /// Return the part of the model due to degrees min_degree..=max_degree.
+    pub fn degree_range(&self, min_degree: usize, max_degree: usize) -> PyResult<Self> {
+        Ok(PyInternalField {
+            field: self
+                .field
+                .degree_range(min_degree, max_degree)
+                .map_err(to_pyerr)?,
+        })
+    }
+
+    /// Split the model into one field per degree.
+    pub fn split_degrees(&self) -> Vec<Self> {
+        self.field
+            .split_degrees()
+            .into_iter()
+            .map(|field| PyInternalField { field })
+            .collect()
+    }
+
+    /// Get the registry metadata of the model, or None for custom coefficients.
+    pub fn model_info<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyDict>>> {
+        self.field
+            .model_info()
+            .map(|info| model_info_to_dict(py, info))
+            .transpose()
+    }
+
+    /// Calculate the field on a regular (theta, phi) grid at a fixed radius.
+    pub fn calc_field_grid<'py>(
+        &self,
+        py: Python<'py>,
+        r: f64,
+        thetas: PyReadonlyArray1<f64>,
+        phis: PyReadonlyArray1<f64>,
+    ) -> Bound<'py, PyArray3<f64>> {
+        self.field
+            .calc_field_grid(r, thetas.as_array(), phis.as_array())
+            .into_pyarray(py)
+    }
+
+    /// Calculate the field on a regular (theta, phi) grid with one radius per theta row.
+    pub fn calc_field_grid_radii<'py>(
+        &self,
+        py: Python<'py>,
+        radii: PyReadonlyArray1<f64>,
+        thetas: PyReadonlyArray1<f64>,
+        phis: PyReadonlyArray1<f64>,
+    ) -> PyResult<Bound<'py, PyArray3<f64>>> {
+        self.field
+            .calc_field_grid_radii(radii.as_array(), thetas.as_array(), phis.as_array())
+            .map(|b| b.into_pyarray(py))
+            .map_err(to_pyerr)
+    }
 }
 
 impl_field_methods!(PyInternalField);
@@ -0,0 +1,67 @@
+use crate::currentsheet::PyCurrentSheetField;
+use crate::internal::PyInternalField;
+use crate::to_pyerr;
+use iupitermag::inversion::{self, Inversion, Regularization};
+use numpy::{IntoPyArray, PyArray2, PyReadonlyArray2};
+use pyo3::exceptions::PyValueError;
+use pyo3::{
+    pyfunction,
+    types::{PyDict, PyDictMethods},
+    Bound, PyResult, Python,
+};
+
+/// Build the design matrix mapping the coefficients up to `degree` to the field at `positions`.
+#[pyfunction]
+pub fn design_matrix<'py>(
+    py: Python<'py>,
+    degree: usize,
+    positions: PyReadonlyArray2<f64>,
+) -> Bound<'py, PyArray2<f64>> {
+    inversion::design_matrix(degree, positions.as_array()).into_pyarray(py)
+}
+
+/// Fit internal field coefficients to observations. `regularization` is one of "none",
+/// "tikhonov" or "field_norm". Returns a dict with the fitted field, residuals and rms.
+#[allow(clippy::too_many_arguments)]
+#[pyfunction]
+pub fn invert_internal_field<'py>(
+    py: Python<'py>,
+    positions: PyReadonlyArray2<f64>,
+    observed: PyReadonlyArray2<f64>,
+    degree: usize,
+    regularization: &str,
+    lambda: f64,
+    radius: f64,
+    currentsheet_field: Option<Bound<'py, PyCurrentSheetField>>,
+) -> PyResult<Bound<'py, PyDict>> {
+    let regularization = match regularization {
+        "none" => Regularization::None,
+        "tikhonov" => Regularization::Tikhonov(lambda),
+        "field_norm" => Regularization::FieldNorm { lambda, radius },
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "Unknown regularization: {regularization}. Supported (none, tikhonov, field_norm)"
+            )))
+        }
+    };
+
+    let currentsheet = currentsheet_field.as_ref().map(|x| x.borrow());
+    let mut problem = Inversion::new(degree).regularization(regularization);
+    if let Some(currentsheet) = &currentsheet {
+        problem = problem.subtract_field(&currentsheet.field);
+    }
+
+    let result = problem
+        .solve(positions.as_array(), observed.as_array())
+        .map_err(to_pyerr)?;
+
+    let field = PyInternalField {
+        field: result.field,
+    };
+
+    let dict = PyDict::new(py);
+    dict.set_item("field", field)?;
+    dict.set_item("residuals", result.residuals.into_pyarray(py))?;
+    dict.set_item("rms", result.rms)?;
+    Ok(dict)
+}
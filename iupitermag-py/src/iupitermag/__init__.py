@@ -1,3 +1,10 @@
 from .currentsheet import CurrentSheetField as CurrentSheetField
+from .external import ExternalHarmonicField as ExternalHarmonicField
+from .field import CompositeField as CompositeField
+from .fit import fit_currentsheet as fit_currentsheet
 from .internal import InternalField as InternalField
+from .internal import available_models as available_models
+from .inversion import design_matrix as design_matrix
+from .inversion import invert_internal_field as invert_internal_field
+from .trace import TraceConfig as TraceConfig
 from .trace import trace_field_to_planet as trace_field_to_planet
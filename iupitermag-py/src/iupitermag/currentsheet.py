@@ -20,11 +20,12 @@ class CurrentSheetField(Field):
                     'd' (scale height),
                     'mu0_i_2' (current),
                     'theta_d' (current sheet inclination with respect to spin axis),
-                    'phi_d' (direction of tilt of the current sheet plane) and,
+                    'phi_d' (System III west longitude of the current sheet tilt) and,
                     'i_rho' (radial current).
 
-            integration_type (str): Type of integration used. Options are 'analytic' (default) or
-                'integral' (not currently implemented).
+            integration_type (str): Type of integration used. Options are 'analytic' (default),
+                'integral' (slower numerical integration of the Bessel function form) or
+                'hybrid' (integral close to the sheet and its inner edge, analytic elsewhere).
         """
         self._field = _iu.PyCurrentSheetField(typefield, params, integration_type)
 
@@ -0,0 +1,64 @@
+import numpy as np
+
+import iupitermag._core as _iu
+
+from .field import Field
+
+
+class ExternalHarmonicField(Field):
+    def __init__(self, q, s, degree=None):
+        """
+        Class for the external (magnetospheric or induced) spherical harmonic
+        terms of a field model, whose degree n field grows as r^(n - 1).
+
+        Args:
+            q (np.array): Schmidt coefficient array q[n, m] in nT
+            s (np.array): Schmidt coefficient array s[n, m] in nT
+            degree (int): Degree to truncate the coefficients to (optional)
+
+        Returns:
+            ExternalHarmonicField class object
+        """
+        self._field = _iu.PyExternalHarmonicField(
+            np.asarray(q, dtype=float), np.asarray(s, dtype=float), degree
+        )
+
+    @classmethod
+    def from_file(cls, path):
+        """
+        Load an external field model from a coefficient file.
+
+        Files ending in ".json" are read as JSON with triangular "q" and "s"
+        arrays, where row n contains m = 0..n. All other files are read as rows
+        of "q n m value" / "s n m value" in nT. Rows of internal coefficients
+        ("g" and "h") are skipped, so the same file can be loaded with
+        InternalField.from_file.
+
+        Args:
+            path (str | os.PathLike): Path to the coefficient file.
+
+        Returns:
+            ExternalHarmonicField class object
+        """
+        field = cls.__new__(cls)
+        field._field = _iu.PyExternalHarmonicField.from_file(path)
+        return field
+
+    def to_file(self, path):
+        """
+        Write the model to a coefficient file, in the same format as from_file.
+
+        Args:
+            path (str | os.PathLike): Path to the coefficient file.
+        """
+        self._field.to_file(path)
+
+    def get_coefficients(self):
+        """
+        Get the coefficients of the external field.
+
+        Returns:
+            q (np.array): Schmidt coefficient (q) in units of nT.
+            s (np.array): Schmidt coefficient (s) in units of nT.
+        """
+        return self._field.get_coefficients()
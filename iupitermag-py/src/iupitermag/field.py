@@ -4,7 +4,12 @@ import iupitermag._core as _iu
 
 
 class Field:
-    _field: _iu.PyInternalField | _iu.PyCurrentSheetField
+    _field: (
+        _iu.PyInternalField
+        | _iu.PyCurrentSheetField
+        | _iu.PyExternalHarmonicField
+        | _iu.PyCompositeField
+    )
 
     def __init__(self):
         """
@@ -111,3 +116,101 @@ class Field:
         return self._field.parmap_calc_field_xyz(
             np.asarray(positions, dtype=float).reshape(-1, 3)
         )
+
+    def calc_jacobian_xyz(self, x, y, z):
+        """
+        Calculate the gradient tensor of the field at a location defined by
+        (x, y, z) in a Cartesian coordinate system.
+
+        Args:
+            x (float): The X coordinate.
+            y (float): The Y coordinate.
+            z (float): The Z coordinate.
+
+        Returns:
+            jacobian (np.ndarray): Array of shape (3, 3) where [i, j] is
+                dB_i / dx_j in nT per planetary radius.
+        """
+        return self._field.calc_jacobian_xyz(x, y, z)
+
+    def map_calc_jacobian_xyz(self, positions):
+        """
+        Calculates the gradient tensor for a collection of N points. Uses
+        cartesian coordinates as input.
+
+        Args:
+            positions (np.ndarray): Array of shape (N, 3), where the last index
+                refers to (x, y, z) coordinates.
+
+        Returns:
+            jacobian (np.ndarray): Array of shape (N, 3, 3) where [n, i, j] is
+                dB_i / dx_j at point n.
+        """
+        return self._field.map_calc_jacobian_xyz(
+            np.asarray(positions, dtype=float).reshape(-1, 3)
+        )
+
+    def parmap_calc_jacobian_xyz(self, positions):
+        """
+        Calculates the gradient tensor for a collection of N points. Uses
+        cartesian coordinates as input and Rayon for parallelization.
+
+        Args:
+            positions (np.ndarray): Array of shape (N, 3), where the last index
+                refers to (x, y, z) coordinates.
+
+        Returns:
+            jacobian (np.ndarray): Array of shape (N, 3, 3) where [n, i, j] is
+                dB_i / dx_j at point n.
+        """
+        return self._field.parmap_calc_jacobian_xyz(
+            np.asarray(positions, dtype=float).reshape(-1, 3)
+        )
+
+    def _sources(self):
+        return [(self, 1.0)]
+
+    def __add__(self, other):
+        if not isinstance(other, Field):
+            return NotImplemented
+        return CompositeField(self._sources() + other._sources())
+
+    def __radd__(self, other):
+        # Allows sum() over a list of fields, which starts from 0.
+        if isinstance(other, (int, float)) and other == 0:
+            return CompositeField(self._sources())
+        return NotImplemented
+
+    def __mul__(self, scale):
+        if not isinstance(scale, (int, float)):
+            return NotImplemented
+        return CompositeField([(field, s * scale) for field, s in self._sources()])
+
+    __rmul__ = __mul__
+
+
+class CompositeField(Field):
+    def __init__(self, sources):
+        """
+        The superposition of several fields, each scaled by a factor. It is
+        usually created by adding fields, e.g. `internal + currentsheet` or
+        `internal + 0.5 * currentsheet`.
+
+        Args:
+            sources (list): List of fields or (field, scale) pairs. Composite
+                fields are flattened into their sources.
+
+        Returns:
+            CompositeField class object
+        """
+        self._scaled_sources = []
+        for source in sources:
+            field, scale = source if isinstance(source, tuple) else (source, 1.0)
+            self._scaled_sources += [(f, s * scale) for f, s in field._sources()]
+
+        self._field = _iu.PyCompositeField(
+            [(field._field, float(scale)) for field, scale in self._scaled_sources]
+        )
+
+    def _sources(self):
+        return list(self._scaled_sources)
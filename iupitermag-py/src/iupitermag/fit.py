@@ -0,0 +1,56 @@
+import numpy as np
+
+import iupitermag._core as _iu
+
+from . import currentsheet, internal
+
+
+def fit_currentsheet(
+    positions,
+    observed,
+    internal_field: internal.InternalField,
+    initial: currentsheet.CurrentSheetField,
+    bounds=None,
+    frozen=None,
+    max_iterations=100,
+):
+    """
+    Fit the parameters of a current sheet model to observations with the
+    Levenberg-Marquardt algorithm, keeping an internal field model fixed.
+
+    Args:
+        positions (np.ndarray): Array of spherical coordinates of shape (N, 3),
+            where the last index refers to (r, theta, phi).
+        observed (np.ndarray): Observed (Br, Btheta, Bphi) in nT of shape (N, 3).
+        internal_field (InternalField): Internal field model accounting for the
+            rest of the observed field.
+        initial (CurrentSheetField): Current sheet whose parameters are the
+            starting point of the fit.
+        bounds (dict): Maps parameter names (e.g. "d", "mu0_i_2") to
+            (lower, upper) bounds (optional).
+        frozen (list): Names of the parameters kept at their initial value
+            (optional).
+        max_iterations (int): Maximum number of iterations.
+
+    Returns:
+        result (dict): "field" is the fitted CurrentSheetField, "params" its
+            parameters, "param_names" the names of the fitted parameters in the
+            order of "covariance", their covariance matrix, "residuals" the
+            observed minus modelled field of shape (N, 3), "rms" the root mean
+            square of the residuals in nT, "iterations" the number of iterations
+            and "converged" whether the fit converged.
+    """
+    result = _iu.fit_currentsheet(
+        np.asarray(positions, dtype=float).reshape(-1, 3),
+        np.asarray(observed, dtype=float).reshape(-1, 3),
+        internal_field._field,
+        initial._field,
+        {} if bounds is None else {k: tuple(map(float, v)) for k, v in bounds.items()},
+        [] if frozen is None else list(frozen),
+        int(max_iterations),
+    )
+
+    field = currentsheet.CurrentSheetField.__new__(currentsheet.CurrentSheetField)
+    field._field = result["field"]
+    result["field"] = field
+    return result
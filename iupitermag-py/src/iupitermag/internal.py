@@ -1,8 +1,33 @@
+import numpy as np
+
 import iupitermag._core as _iu
 
 from .field import Field
 
 
+SECONDS_PER_JULIAN_YEAR = 365.25 * 86400.0
+
+
+def _decimal_year(time, j2000):
+    """Convert seconds from J2000 to decimal (Julian) years if j2000 is set."""
+    time = np.asarray(time, dtype=float)
+    return 2000.0 + time / SECONDS_PER_JULIAN_YEAR if j2000 else time
+
+
+def available_models():
+    """
+    List the internal field models known to iupitermag.
+
+    Returns:
+        models (list[dict]): One dict per model with keys "name", "reference",
+            "max_degree", "recommended_degree", "planet_radius_km" and "bundled".
+            Models that are not bundled are listed for their metadata only:
+            InternalField(name) raises a ValueError for them, and their
+            coefficients have to be given as custom coefficients instead.
+    """
+    return _iu.available_models()
+
+
 class InternalField(Field):
     def __init__(self, typefield="", g=None, h=None, degree=None):
         """
@@ -10,7 +35,7 @@ class InternalField(Field):
 
         Args:
             typefield (str): Type of planet field. Allowed values are
-                "JRM09", "JRM33", "Custom"
+                "Custom" or the name of a bundled model, see available_models()
             g (np.array): Legendre coefficient array g[n ,m]
             h (np.array): Legendre coefficient array h[n, m]
             degree (int): Degree of field
@@ -22,6 +47,379 @@ class InternalField(Field):
         """
         self._field = _iu.PyInternalField(typefield, g, h, degree)
 
+    @classmethod
+    def from_file(cls, path):
+        """
+        Load an internal field model from a coefficient file.
+
+        Files ending in ".json" are read as JSON with optional model metadata
+        ("name", "reference", "planet_radius_km", "recommended_degree") and
+        triangular "g" and "h" arrays, where row n contains m = 0..n. All other
+        files are read as rows of "g n m value" / "h n m value" in nT, with
+        "#" starting a comment. Each coefficient may only appear once, and "h"
+        rows need m > 0. Rows of external coefficients ("q" and "s") are
+        skipped, see ExternalHarmonicField.from_file.
+
+        Args:
+            path (str | os.PathLike): Path to the coefficient file.
+
+        Returns:
+            InternalField class object
+        """
+        field = cls.__new__(cls)
+        field._field = _iu.PyInternalField.from_file(path)
+        return field
+
+    def to_file(self, path):
+        """
+        Write the model to a coefficient file. The format is chosen from the
+        file extension in the same way as from_file, so the file can be read
+        back with from_file. Only JSON files keep the model metadata; text
+        files hold the name as a comment that is not read back.
+
+        Args:
+            path (str | os.PathLike): Path to the coefficient file.
+        """
+        self._field.to_file(path)
+
+    def model_info(self):
+        """
+        Get the registry metadata of this model.
+
+        Returns:
+            info (dict | None): Model metadata (see available_models), or None
+                for custom coefficients.
+        """
+        return self._field.model_info()
+
+    def with_secular_variation(self, dg, dh, epoch, j2000=False):
+        """
+        Return a copy of the model with secular variation.
+
+        Args:
+            dg (np.array): Rate of change of g[n, m] in nT / year.
+            dh (np.array): Rate of change of h[n, m] in nT / year.
+            epoch (float): Time at which the main field coefficients apply, as a
+                decimal year, or seconds from J2000 if j2000 is True.
+            j2000 (bool): Whether epoch is given in seconds from J2000.
+
+        Returns:
+            InternalField class object
+        """
+        field = self.__class__.__new__(self.__class__)
+        field._field = self._field.with_secular_variation(
+            np.asarray(dg, dtype=float),
+            np.asarray(dh, dtype=float),
+            float(_decimal_year(epoch, j2000)),
+        )
+        return field
+
+    def epoch(self):
+        """
+        Get the epoch of the model as a decimal year, or None if the model
+        has no secular variation.
+        """
+        return self._field.epoch()
+
+    def get_secular_variation(self):
+        """
+        Get the secular variation coefficients of the model.
+
+        Returns:
+            dg, dh (np.array, np.array): Rates of change of g and h in
+                nT / year, or None if the model has no secular variation.
+        """
+        return self._field.get_secular_variation()
+
+    def at_time(self, time, j2000=False):
+        """
+        Return the model evolved linearly to a given time.
+
+        Args:
+            time (float): Decimal year, or seconds from J2000 if j2000 is True.
+            j2000 (bool): Whether time is given in seconds from J2000.
+
+        Returns:
+            InternalField class object
+        """
+        field = self.__class__.__new__(self.__class__)
+        field._field = self._field.at_time(float(_decimal_year(time, j2000)))
+        return field
+
+    def calc_field_at_time(self, r, theta, phi, time, j2000=False):
+        """
+        Calculate [Br, Btheta, Bphi] at a location and time, evolving the model
+        linearly from its epoch.
+
+        Args:
+            r (float): Radius in planetary radii
+            theta (float): Co-latitude in radians
+            phi (float): Azimuth in radians
+            time (float): Decimal year, or seconds from J2000 if j2000 is True.
+            j2000 (bool): Whether time is given in seconds from J2000.
+
+        Returns:
+            field (np.array): Magnetic field components [Br, Btheta, Bphi].
+        """
+        return self._field.calc_field_at_time(
+            r, theta, phi, float(_decimal_year(time, j2000))
+        )
+
+    def map_calc_field_at_time(self, positions, times, j2000=False, parallel=True):
+        """
+        Calculates [Br, Btheta, Bphi] for a collection of N points, each at its
+        own time, e.g. along a multi-year orbit series.
+
+        Args:
+            positions (np.ndarray): Array of spherical coordinates of shape (N, 3),
+                where the last index refers to (r, theta, phi).
+            times (np.ndarray | float): Array of shape (N,) of decimal years, or
+                seconds from J2000 if j2000 is True. A single time applies to
+                all the points.
+            j2000 (bool): Whether times are given in seconds from J2000.
+            parallel (bool): Whether to use Rayon for parallelization.
+
+        Returns:
+            field_rtp (np.ndarray): Array of (Br, Btheta, Bphi) of shape (N, 3).
+        """
+        positions = np.asarray(positions, dtype=float).reshape(-1, 3)
+        times = np.broadcast_to(_decimal_year(times, j2000).ravel(), (len(positions),))
+
+        if parallel:
+            return self._field.parmap_calc_field_at_time(positions, times)
+        return self._field.map_calc_field_at_time(positions, times)
+
+    def dipole(self):
+        """
+        Get the properties of the dipole of the model, derived from its degree
+        1 and 2 coefficients.
+
+        Returns:
+            dipole (dict): With keys
+                "b0" (equatorial surface field of the dipole in nT),
+                "moment" (dipole moment in A m^2),
+                "tilt" (angle from the spin axis in radians),
+                "pole_longitude" (System III west longitude in radians) and
+                "offset" (eccentric dipole position (x, y, z) in planetary radii).
+
+            "tilt" and "pole_longitude" can be used as "theta_d" and "phi_d" of a
+            CurrentSheetField.
+        """
+        return self._field.dipole()
+
+    def rotate(self, rotation):
+        """
+        Express the model in a rotated frame, so that analyses can be done in
+        that frame without converting every position and field vector.
+
+        Args:
+            rotation (np.ndarray): Rotation matrix of shape (3, 3) that rotates
+                cartesian vectors from the current frame into the new frame,
+                x_new = rotation @ x.
+
+        Returns:
+            InternalField class object, with B_new(rotation @ x) = rotation @ B(x).
+        """
+        field = self.__class__.__new__(self.__class__)
+        field._field = self._field.rotate(np.asarray(rotation, dtype=float))
+        return field
+
+    def in_dipole_frame(self):
+        """
+        Express the model in the MAG frame of its own dipole (see dipole()),
+        in which the dipole is aligned with the z axis.
+
+        Returns:
+            InternalField class object
+        """
+        field = self.__class__.__new__(self.__class__)
+        field._field = self._field.in_dipole_frame()
+        return field
+
+    def power_spectrum(self, r=1.0):
+        """
+        Calculate the Lowes-Mauersberger spatial power spectrum
+        R_n = (n + 1) (1 / r)^(2n + 4) sum_m (g[n, m]^2 + h[n, m]^2).
+
+        Args:
+            r (float): Radius in planetary radii at which to evaluate the
+                spectrum, e.g. below the surface for a dynamo radius candidate.
+
+        Returns:
+            spectrum (np.ndarray): R_n in nT^2 of shape (degree + 1,), indexed
+                by degree n.
+        """
+        return self._field.power_spectrum(r)
+
+    def degree_range(self, min_degree, max_degree):
+        """
+        Return the part of the model due to a range of degrees only, e.g.
+        degree_range(1, 1) for the dipole or degree_range(2, 5).
+
+        Args:
+            min_degree (int): Lowest degree to keep.
+            max_degree (int): Highest degree to keep (inclusive).
+
+        Returns:
+            InternalField class object
+        """
+        field = self.__class__.__new__(self.__class__)
+        field._field = self._field.degree_range(min_degree, max_degree)
+        return field
+
+    def split_degrees(self):
+        """
+        Split the model into one field per degree.
+
+        Returns:
+            fields (list[InternalField]): Entry n contains only degree n.
+        """
+        fields = []
+        for part in self._field.split_degrees():
+            field = self.__class__.__new__(self.__class__)
+            field._field = part
+            fields.append(field)
+        return fields
+
+    def calc_potential(self, r, theta, phi):
+        """
+        Calculate the magnetic scalar potential V at a location, such that
+        B = -grad V.
+
+        Args:
+            r (float): Radius in planetary radii
+            theta (float): Co-latitude in radians
+            phi (float): Azimuth in radians
+
+        Returns:
+            potential (float): Scalar potential in nT planetary radii.
+        """
+        return self._field.calc_potential(r, theta, phi)
+
+    def map_calc_potential(self, positions):
+        """
+        Calculates the magnetic scalar potential for a collection of N points.
+
+        Args:
+            positions (np.ndarray): Array of spherical coordinates of shape (N, 3),
+                where the last index refers to (r, theta, phi).
+
+        Returns:
+            potential (np.ndarray): Scalar potential in nT planetary radii of
+                shape (N,).
+        """
+        return self._field.map_calc_potential(
+            np.asarray(positions, dtype=float).reshape(-1, 3)
+        )
+
+    def parmap_calc_potential(self, positions):
+        """
+        Calculates the magnetic scalar potential for a collection of N points
+        using Rayon for parallelization.
+
+        Args:
+            positions (np.ndarray): Array of spherical coordinates of shape (N, 3),
+                where the last index refers to (r, theta, phi).
+
+        Returns:
+            potential (np.ndarray): Scalar potential in nT planetary radii of
+                shape (N,).
+        """
+        return self._field.parmap_calc_potential(
+            np.asarray(positions, dtype=float).reshape(-1, 3)
+        )
+
+    def with_covariance(self, covariance):
+        """
+        Return a copy of the model with a coefficient covariance matrix. Rows
+        and columns are ordered g10, g11, h11, g20, g21, h21, g22, h22, ...
+
+        Args:
+            covariance (np.ndarray): Covariance in nT^2 of shape (K, K), with
+                K = degree * (degree + 2).
+
+        Returns:
+            InternalField class object
+        """
+        field = self.__class__.__new__(self.__class__)
+        field._field = self._field.with_covariance(
+            np.asarray(covariance, dtype=float)
+        )
+        return field
+
+    def with_standard_deviations(self, sigma_g, sigma_h):
+        """
+        Return a copy of the model with uncorrelated coefficient uncertainties.
+
+        Args:
+            sigma_g (np.array): 1-sigma uncertainty of g[n, m] in nT.
+            sigma_h (np.array): 1-sigma uncertainty of h[n, m] in nT.
+
+        Returns:
+            InternalField class object
+        """
+        field = self.__class__.__new__(self.__class__)
+        field._field = self._field.with_standard_deviations(
+            np.asarray(sigma_g, dtype=float), np.asarray(sigma_h, dtype=float)
+        )
+        return field
+
+    def covariance(self):
+        """
+        Get the coefficient covariance matrix in nT^2, or None if the model has
+        none. See with_covariance for the ordering.
+        """
+        return self._field.covariance()
+
+    def design_matrix_rows(self, r, theta, phi):
+        """
+        Derivatives of [Br, Btheta, Bphi] at a location with respect to each
+        coefficient, in the order of with_covariance.
+
+        Args:
+            r (float): Radius in planetary radii
+            theta (float): Co-latitude in radians
+            phi (float): Azimuth in radians
+
+        Returns:
+            rows (np.ndarray): Array of shape (3, K).
+        """
+        return self._field.design_matrix_rows(r, theta, phi)
+
+    def calc_field_uncertainty(self, r, theta, phi):
+        """
+        Calculate the 1-sigma uncertainty of [Br, Btheta, Bphi] at a location
+        due to the coefficient covariance.
+
+        Args:
+            r (float): Radius in planetary radii
+            theta (float): Co-latitude in radians
+            phi (float): Azimuth in radians
+
+        Returns:
+            sigma_rtp (np.ndarray): Uncertainty of (Br, Btheta, Bphi) in nT.
+        """
+        return self._field.calc_field_uncertainty(r, theta, phi)
+
+    def map_calc_field_uncertainty(self, positions, parallel=True):
+        """
+        Calculates the uncertainty of [Br, Btheta, Bphi] for a collection of N
+        points, e.g. error bars along a spacecraft trajectory.
+
+        Args:
+            positions (np.ndarray): Array of spherical coordinates of shape (N, 3),
+                where the last index refers to (r, theta, phi).
+            parallel (bool): Whether to use Rayon for parallelization.
+
+        Returns:
+            sigma_rtp (np.ndarray): Array of uncertainties of shape (N, 3).
+        """
+        positions = np.asarray(positions, dtype=float).reshape(-1, 3)
+
+        if parallel:
+            return self._field.parmap_calc_field_uncertainty(positions)
+        return self._field.map_calc_field_uncertainty(positions)
+
     def get_coefficients(self):
         """
         Get the coeffiicients of the defined internal field.
@@ -31,3 +429,31 @@ class InternalField(Field):
             h (np.array): Legendre coefficient (h) in units of nT.
         """
         return self._field.get_coefficients()
+
+    def calc_field_grid(self, r, thetas, phis):
+        """
+        Calculate [Br, Btheta, Bphi] on a regular grid of co-latitudes and
+        azimuths. This is much faster than map_calc_field for surface maps,
+        since the Legendre polynomials are only computed once per theta and
+        the trigonometric terms once per phi.
+
+        Args:
+            r (float | np.ndarray): Radius in planetary radii. Either a single
+                value, or an array of shape (Ntheta,) with one radius per theta
+                (e.g. for an oblate surface).
+            thetas (np.ndarray): Co-latitudes in radians, shape (Ntheta,).
+            phis (np.ndarray): Azimuths in radians, shape (Nphi,).
+
+        Returns:
+            field_rtp (np.ndarray): Array of (Br, Btheta, Bphi) of shape
+                (Ntheta, Nphi, 3).
+        """
+        thetas = np.asarray(thetas, dtype=float).ravel()
+        phis = np.asarray(phis, dtype=float).ravel()
+
+        if np.ndim(r) == 0:
+            return self._field.calc_field_grid(float(r), thetas, phis)
+
+        return self._field.calc_field_grid_radii(
+            np.asarray(r, dtype=float).ravel(), thetas, phis
+        )
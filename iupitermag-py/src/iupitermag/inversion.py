@@ -0,0 +1,73 @@
+import numpy as np
+
+import iupitermag._core as _iu
+
+from . import currentsheet, internal
+
+
+def design_matrix(degree, positions):
+    """
+    Build the design matrix that maps the internal field coefficients to
+    [Br, Btheta, Bphi] at a collection of N points. The coefficients are
+    ordered g10, g11, h11, g20, g21, h21, g22, h22, ...
+
+    Args:
+        degree (int): Maximum degree of the coefficients.
+        positions (np.ndarray): Array of spherical coordinates of shape (N, 3),
+            where the last index refers to (r, theta, phi).
+
+    Returns:
+        matrix (np.ndarray): Array of shape (3N, degree * (degree + 2)), where
+            rows 3i, 3i + 1 and 3i + 2 are Br, Btheta and Bphi at point i.
+    """
+    return _iu.design_matrix(
+        int(degree), np.asarray(positions, dtype=float).reshape(-1, 3)
+    )
+
+
+def invert_internal_field(
+    positions,
+    observed,
+    degree,
+    regularization="none",
+    lam=0.0,
+    radius=1.0,
+    currentsheet_field: currentsheet.CurrentSheetField | None = None,
+):
+    """
+    Fit the coefficients of an internal field model to observations by
+    regularized least squares.
+
+    Args:
+        positions (np.ndarray): Array of spherical coordinates of shape (N, 3),
+            where the last index refers to (r, theta, phi).
+        observed (np.ndarray): Observed (Br, Btheta, Bphi) in nT of shape (N, 3).
+        degree (int): Maximum degree of the fitted model.
+        regularization (str): "none", "tikhonov" to penalize the sum of the
+            squared coefficients, or "field_norm" to penalize the mean square
+            field over the sphere of the given radius.
+        lam (float): Weight of the regularization.
+        radius (float): Radius in planetary radii for "field_norm".
+        currentsheet_field (CurrentSheetField): Current sheet field to subtract
+            from the observations before the fit (optional).
+
+    Returns:
+        result (dict): "field" is the fitted InternalField, with the covariance
+            of its coefficients attached, "residuals" the observed minus
+            modelled field of shape (N, 3), and "rms" the root mean square of
+            the residuals in nT.
+    """
+    result = _iu.invert_internal_field(
+        np.asarray(positions, dtype=float).reshape(-1, 3),
+        np.asarray(observed, dtype=float).reshape(-1, 3),
+        int(degree),
+        regularization,
+        float(lam),
+        float(radius),
+        None if currentsheet_field is None else currentsheet_field._field,
+    )
+
+    field = internal.InternalField.__new__(internal.InternalField)
+    field._field = result["field"]
+    result["field"] = field
+    return result
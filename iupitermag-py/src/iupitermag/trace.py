@@ -2,39 +2,122 @@ import numpy as np
 
 import iupitermag._core as _iu
 
-from . import currentsheet, internal
+from . import currentsheet, field, internal
+
+# Default of `currentsheet_field`, which depends on the kind of `internal_field`.
+_DEFAULT_CURRENTSHEET = object()
+
+
+class TraceConfig:
+    def __init__(
+        self,
+        initial_step=None,
+        max_step=None,
+        absolute_tolerance=None,
+        relative_tolerance=None,
+        method=None,
+        outer_boundary=None,
+        equatorial_radius=None,
+        flattening=None,
+        max_steps=None,
+    ):
+        """
+        Settings of the field line tracer. Distances are in planetary radii and
+        steps are arc lengths along the field line. Settings that are not given
+        keep their default.
+
+        Args:
+            initial_step (float): Size of the first step (default 0.025).
+            max_step (float): Largest step of the adaptive integrator (default 0.25).
+            absolute_tolerance (float): Absolute tolerance of the step size control
+                (default 1e-4).
+            relative_tolerance (float): Relative tolerance of the step size control
+                (default 1e-4).
+            method (str): Runge-Kutta method. Options are 'dormand_prince' (default),
+                'fehlberg', 'bogacki_shampine' or 'heun_euler'.
+            outer_boundary (float): Radius beyond which tracing stops (default 200).
+            equatorial_radius (float): Equatorial radius of the inner boundary ellipsoid
+                (default 1).
+            flattening (float): Flattening of the inner boundary ellipsoid, 0 for a
+                sphere (default 1/15.4).
+            max_steps (int): Maximum number of steps in each direction, which
+                stops field lines that never leave the domain (default 10^6).
+        """
+
+        def optional(convert, value):
+            return None if value is None else convert(value)
+
+        self._config = _iu.PyTraceConfig(
+            optional(float, initial_step),
+            optional(float, max_step),
+            optional(float, absolute_tolerance),
+            optional(float, relative_tolerance),
+            method,
+            optional(float, outer_boundary),
+            optional(float, equatorial_radius),
+            optional(float, flattening),
+            optional(int, max_steps),
+        )
 
 
 def trace_field_to_planet(
     start_positions: np.ndarray,
-    internal_field: str | internal.InternalField = "JRM33",
-    currentsheet_field: str | currentsheet.CurrentSheetField = "CON2020",
+    internal_field: str | field.Field = "JRM33",
+    currentsheet_field: str | currentsheet.CurrentSheetField | None = _DEFAULT_CURRENTSHEET,
+    config: TraceConfig | None = None,
+    parallel: bool = True,
 ):
     """
-    Trace the magnetic field from a collection of points to the planet.
+    Trace the magnetic field from a collection of points to the planet. The GIL
+    is released while tracing.
 
     Args:
         start_positions (np.ndarray): Array of shape (N, 3) where N is the number
             of points and indices [:,0], [:, 1], and [:, 2] represent the X, Y,
             and Z coordinates of each point in the IAU coordinate system.
-        internal_field (str | internal.InternalField): The internal field to use (default="JRM33").
-        currentsheet_field (str | currentsheet.CurrentSheetField): The current sheet field to use
-            (default="CON2020").
+        internal_field (str | field.Field): The internal field to use (default="JRM33"). Any
+            field, such as a CompositeField, can be given here to trace it instead.
+        currentsheet_field (str | currentsheet.CurrentSheetField | None): The current sheet field
+            to add, or None to trace the internal field alone. Defaults to "CON2020" when
+            internal_field is a model name or an InternalField, and to None for any other
+            field, so that a combined field is traced as it is.
+        config (TraceConfig): Settings of the tracer (optional).
+        parallel (bool): Whether to trace the starting points in parallel using
+            Rayon (default=True).
 
     Returns:
-        traces (list[np.ndarray]): List of traces where each trace is of shape (M, 3) where M is
-            the number of coordinates of each trace.  Each point of the trace is described by its
-            cartesian representation in the IAU coordinate system.  In total there should be N
-            traces, one for each starting position.
+        traces (list[dict]): List of N traces, one for each starting position. Each
+            trace is a dict with
+                "positions": np.ndarray of shape (M, 3), the cartesian coordinates of
+                    the entire field line in the IAU coordinate system, from the end
+                    of the north segment to the end of the south segment,
+                "length": the total arc length of the field line,
+                "topology": "closed" if both ends reach the planet, "open" if one
+                    end reaches the planet and the other the outer boundary, and
+                    "unknown" otherwise,
+                "north" and "south": the segments traced against and along the field
+                    from the starting point. Each is a dict with "positions" (K, 3),
+                    "arc_length" (K,) from the starting point, "b" (K, 3) the
+                    cartesian field in nT, "b_magnitude" (K,) and "termination",
+                    one of "planet", "outer_boundary", "max_steps" or "null_field".
     """
+    if currentsheet_field is _DEFAULT_CURRENTSHEET:
+        is_internal = isinstance(internal_field, (str, internal.InternalField))
+        currentsheet_field = "CON2020" if is_internal else None
+
     if isinstance(internal_field, str):
         internal_field = internal.InternalField(internal_field)
 
     if isinstance(currentsheet_field, str):
         currentsheet_field = currentsheet.CurrentSheetField(currentsheet_field)
 
+    traced_field = field.CompositeField(
+        [internal_field] if currentsheet_field is None else [internal_field, currentsheet_field]
+    )
+
     return _iu.trace_field_to_planet(
         np.asarray(start_positions, dtype=float).reshape(-1, 3),
-        internal_field._field,
-        currentsheet_field._field,
+        traced_field._field,
+        None if config is None else config._config,
+        parallel,
     )
@@ -1,9 +1,18 @@
 pub mod currentsheet;
+pub mod external;
 pub mod field;
+pub mod fit;
 pub mod internal;
+pub mod inversion;
+pub mod models;
 pub mod trace;
 
-use pyo3::pymodule;
+use pyo3::{PyErr, exceptions::PyValueError, pymodule};
+
+/// Convert an error from the core crate into a Python `ValueError`.
+pub fn to_pyerr(e: iupitermag::Error) -> PyErr {
+    PyValueError::new_err(e.to_string())
+}
 
 #[pymodule]
 mod _core {
@@ -13,6 +22,27 @@ mod _core {
     #[pymodule_export]
     pub use crate::currentsheet::PyCurrentSheetField;
 
+    #[pymodule_export]
+    pub use crate::external::PyExternalHarmonicField;
+
+    #[pymodule_export]
+    pub use crate::field::PyCompositeField;
+
+    #[pymodule_export]
+    pub use crate::trace::PyTraceConfig;
+
     #[pymodule_export]
     pub use crate::trace::trace_field_to_planet;
+
+    #[pymodule_export]
+    pub use crate::models::available_models;
+
+    #[pymodule_export]
+    pub use crate::inversion::design_matrix;
+
+    #[pymodule_export]
+    pub use crate::inversion::invert_internal_field;
+
+    #[pymodule_export]
+    pub use crate::fit::fit_currentsheet;
 }
@@ -0,0 +1,27 @@
+use iupitermag::models::{self, ModelInfo};
+use pyo3::{
+    pyfunction,
+    types::{PyDict, PyDictMethods, PyList},
+    Bound, PyResult, Python,
+};
+
+/// Convert the metadata of a model into a Python dict.
+pub fn model_info_to_dict<'py>(py: Python<'py>, info: &ModelInfo) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("name", info.name.as_ref())?;
+    dict.set_item("reference", info.reference.as_ref())?;
+    dict.set_item("max_degree", info.max_degree)?;
+    dict.set_item("recommended_degree", info.recommended_degree)?;
+    dict.set_item("planet_radius_km", info.planet_radius_km)?;
+    dict.set_item("bundled", info.bundled)?;
+    Ok(dict)
+}
+
+#[pyfunction]
+pub fn available_models<'py>(py: Python<'py>) -> PyResult<Bound<'py, PyList>> {
+    let models = models::available_models()
+        .iter()
+        .map(|info| model_info_to_dict(py, info))
+        .collect::<PyResult<Vec<_>>>()?;
+    PyList::new(py, models)
+}
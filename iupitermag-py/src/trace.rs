@@ -1,29 +1,161 @@
-use crate::currentsheet::PyCurrentSheetField;
-use crate::internal::PyInternalField;
-use iupitermag::trace;
+use crate::field::PyCompositeField;
+use crate::to_pyerr;
+use iupitermag::trace::{
+    self, Termination, Topology, Trace, TraceConfig, TraceMethod, TraceSegment,
+};
 use numpy::{IntoPyArray, PyReadonlyArray2};
-use pyo3::{pyfunction, types::PyList, Bound, PyResult, Python};
+use pyo3::exceptions::PyValueError;
+use pyo3::{
+    pyclass, pyfunction, pymethods,
+    types::{PyDict, PyDictMethods, PyList},
+    Bound, PyResult, Python,
+};
 use std::f64;
 
+#[pyclass]
+pub struct PyTraceConfig {
+    pub config: TraceConfig,
+}
+
+#[pymethods]
+impl PyTraceConfig {
+    #[allow(clippy::too_many_arguments)]
+    #[new]
+    #[pyo3(signature = (
+        initial_step=None,
+        max_step=None,
+        absolute_tolerance=None,
+        relative_tolerance=None,
+        method=None,
+        outer_boundary=None,
+        equatorial_radius=None,
+        flattening=None,
+        max_steps=None,
+    ))]
+    pub fn __init__(
+        initial_step: Option<f64>,
+        max_step: Option<f64>,
+        absolute_tolerance: Option<f64>,
+        relative_tolerance: Option<f64>,
+        method: Option<&str>,
+        outer_boundary: Option<f64>,
+        equatorial_radius: Option<f64>,
+        flattening: Option<f64>,
+        max_steps: Option<usize>,
+    ) -> PyResult<Self> {
+        // Settings that are not given keep their value from `TraceConfig::default()`.
+        let mut config = TraceConfig::default();
+
+        if let Some(initial_step) = initial_step {
+            config = config.initial_step(initial_step);
+        }
+        if let Some(max_step) = max_step {
+            config = config.max_step(max_step);
+        }
+        let (absolute, relative) = config.get_tolerances();
+        config = config.tolerances(
+            absolute_tolerance.unwrap_or(absolute),
+            relative_tolerance.unwrap_or(relative),
+        );
+        if let Some(method) = method {
+            let method = match method.to_lowercase().as_str() {
+                "heun_euler" => TraceMethod::HeunEuler,
+                "bogacki_shampine" => TraceMethod::BogackiShampine,
+                "fehlberg" => TraceMethod::Fehlberg,
+                "dormand_prince" => TraceMethod::DormandPrince,
+                _ => {
+                    return Err(PyValueError::new_err(
+                        "Unrecognized method. Allowed - heun_euler, bogacki_shampine, fehlberg, dormand_prince .",
+                    ));
+                }
+            };
+            config = config.method(method);
+        }
+        if let Some(outer_boundary) = outer_boundary {
+            config = config.outer_boundary(outer_boundary);
+        }
+        let (radius, flat) = config.get_inner_boundary();
+        config = config.inner_boundary(
+            equatorial_radius.unwrap_or(radius),
+            flattening.unwrap_or(flat),
+        );
+        if let Some(max_steps) = max_steps {
+            config = config.max_steps(max_steps);
+        }
+        config.validate().map_err(to_pyerr)?;
+
+        Ok(PyTraceConfig { config })
+    }
+}
+
+/// Trace field lines from each row of `positions`, releasing the GIL while tracing. With
+/// `parallel`, the starting points are spread across threads with Rayon.
 #[pyfunction]
+#[pyo3(signature = (positions, field, config=None, parallel=true))]
 pub fn trace_field_to_planet<'py>(
     py: Python<'py>,
     positions: PyReadonlyArray2<f64>,
-    internal_field: Bound<'py, PyInternalField>,
-    currentsheet_field: Bound<'py, PyCurrentSheetField>,
+    field: Bound<'py, PyCompositeField>,
+    config: Option<Bound<'py, PyTraceConfig>>,
+    parallel: bool,
 ) -> PyResult<Bound<'py, PyList>> {
     let pos_arr = positions.as_array();
 
-    let mut traces = vec![];
+    let field = field.borrow();
+    let field = &field.field;
+    let config = config.map_or_else(TraceConfig::default, |x| x.borrow().config.clone());
 
-    let internal = internal_field.borrow();
-    let currentsheet = currentsheet_field.borrow();
+    let traces = py
+        .detach(|| {
+            if parallel {
+                trace::par_trace_field_to_planet(pos_arr, field, &config)
+            } else {
+                pos_arr
+                    .rows()
+                    .into_iter()
+                    .map(|pos| trace::trace_field_to_planet(pos.to_owned(), field, &config))
+                    .collect()
+            }
+        })
+        .map_err(to_pyerr)?;
 
-    for pos in pos_arr.rows() {
-        traces.push(
-            trace::trace_field_to_planet(pos.to_owned(), &internal.field, &currentsheet.field)
-                .into_pyarray(py),
-        )
-    }
+    let traces = traces
+        .into_iter()
+        .map(|x| trace_to_dict(py, x))
+        .collect::<PyResult<Vec<_>>>()?;
     PyList::new(py, traces)
 }
+
+/// Convert a trace into a dict with its full positions, segments and topology.
+fn trace_to_dict(py: Python<'_>, trace: Trace) -> PyResult<Bound<'_, PyDict>> {
+    let topology = match trace.topology() {
+        Topology::Closed => "closed",
+        Topology::Open => "open",
+        Topology::Unknown => "unknown",
+    };
+
+    let dict = PyDict::new(py);
+    dict.set_item("positions", trace.positions().into_pyarray(py))?;
+    dict.set_item("length", trace.length())?;
+    dict.set_item("topology", topology)?;
+    dict.set_item("north", segment_to_dict(py, trace.north)?)?;
+    dict.set_item("south", segment_to_dict(py, trace.south)?)?;
+    Ok(dict)
+}
+
+fn segment_to_dict(py: Python<'_>, segment: TraceSegment) -> PyResult<Bound<'_, PyDict>> {
+    let termination = match segment.termination {
+        Termination::Planet => "planet",
+        Termination::OuterBoundary => "outer_boundary",
+        Termination::MaxSteps => "max_steps",
+        Termination::NullField => "null_field",
+    };
+
+    let dict = PyDict::new(py);
+    dict.set_item("positions", segment.positions.into_pyarray(py))?;
+    dict.set_item("arc_length", segment.arc_length.into_pyarray(py))?;
+    dict.set_item("b", segment.b.into_pyarray(py))?;
+    dict.set_item("b_magnitude", segment.b_magnitude.into_pyarray(py))?;
+    dict.set_item("termination", termination)?;
+    Ok(dict)
+}
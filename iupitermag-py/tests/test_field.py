@@ -1,4 +1,5 @@
 import numpy as np
+import pytest
 
 import iupitermag
 
@@ -28,3 +29,299 @@ def test_currentsheet_field():
     b_calc = currentsheet_field.calc_field_xyz(x, y, z)
     b_expected = np.array([-31.20, 1.53, 23.60])
     assert np.allclose(b_expected, b_calc, rtol=1e-3)
+
+
+def test_invalid_field_raises():
+    with pytest.raises(ValueError):
+        iupitermag.InternalField("JRM99")
+
+    with pytest.raises(ValueError):
+        iupitermag.CurrentSheetField("Custom", {"r_0": 7.8})
+
+
+def test_internal_field_grid():
+    internal_field = iupitermag.InternalField("JRM33", degree=13)
+
+    thetas = np.linspace(0.1, 3.0, 5)
+    phis = np.linspace(0.0, 2 * np.pi, 7)
+
+    b_grid = internal_field.calc_field_grid(1.5, thetas, phis)
+    assert b_grid.shape == (5, 7, 3)
+
+    theta, phi = np.meshgrid(thetas, phis, indexing="ij")
+    pos = np.stack([np.full(theta.size, 1.5), theta.ravel(), phi.ravel()], axis=1)
+    b_expected = internal_field.map_calc_field(pos).reshape(5, 7, 3)
+    assert np.allclose(b_expected, b_grid)
+
+
+def test_available_models():
+    models = {m["name"]: m for m in iupitermag.available_models()}
+
+    assert models["JRM33"]["recommended_degree"] == 13
+    assert models["VIP4"]["bundled"]
+    assert not models["ISaAC"]["bundled"]
+
+    internal_field = iupitermag.InternalField("JRM33_13")
+    assert internal_field.model_info()["max_degree"] == 13
+    assert internal_field.get_coefficients()[0].shape == (14, 14)
+
+
+@pytest.mark.parametrize("suffix", [".txt", ".json"])
+def test_internal_field_file_round_trip(tmp_path, suffix):
+    internal_field = iupitermag.InternalField("JRM09")
+    path = tmp_path / f"jrm09{suffix}"
+    internal_field.to_file(path)
+
+    loaded = iupitermag.InternalField.from_file(path)
+
+    for x, x_expected in zip(loaded.get_coefficients(), internal_field.get_coefficients()):
+        assert np.allclose(x, x_expected)
+
+
+def test_internal_field_secular_variation():
+    dg = np.zeros((2, 2))
+    dh = np.zeros((2, 2))
+    dg[1, 0] = 10.0
+
+    internal_field = iupitermag.InternalField("JRM09").with_secular_variation(
+        dg, dh, 2020.0
+    )
+    assert internal_field.epoch() == 2020.0
+
+    g_evolved = internal_field.at_time(2022.0).get_coefficients()[0]
+    g = internal_field.get_coefficients()[0]
+    assert np.isclose(g_evolved[1, 0] - g[1, 0], 20.0)
+
+    b_calc = internal_field.calc_field_at_time(3.0, 1.0, 0.5, 2022.0)
+    b_expected = internal_field.at_time(2022.0).calc_field(3.0, 1.0, 0.5)
+    assert np.allclose(b_calc, b_expected)
+
+    # 2022.0 is 22 Julian years after J2000.
+    seconds = 22 * 365.25 * 86400.0
+    b_calc = internal_field.map_calc_field_at_time(
+        [[3.0, 1.0, 0.5]], [seconds], j2000=True
+    )
+    assert np.allclose(b_calc[0], b_expected)
+
+    # A single time applies to all the points, and a mismatched number of times is an error.
+    b_calc = internal_field.map_calc_field_at_time(
+        [[3.0, 1.0, 0.5], [3.0, 1.0, 0.5]], 2022.0
+    )
+    assert np.allclose(b_calc, [b_expected, b_expected])
+
+    with pytest.raises(ValueError):
+        internal_field.map_calc_field_at_time(
+            [[3.0, 1.0, 0.5], [3.0, 1.0, 0.5], [3.0, 1.0, 0.5]], [2021.0, 2022.0]
+        )
+
+
+def test_jacobian_divergence_free():
+    internal_field = iupitermag.InternalField("JRM33", degree=13)
+
+    positions = np.array([[1.2, -0.4, 0.7], [-3.0, 2.0, -5.0]])
+    jacobian = internal_field.parmap_calc_jacobian_xyz(positions)
+    assert jacobian.shape == (2, 3, 3)
+
+    divergence = np.trace(jacobian, axis1=1, axis2=2)
+    assert np.allclose(divergence, 0.0, atol=1e-6 * np.abs(jacobian).max())
+
+
+def test_internal_field_potential():
+    internal_field = iupitermag.InternalField("JRM09")
+
+    r, theta, phi = 1.7, 1.1, 4.2
+    step = 1e-5
+    b_r = -(
+        internal_field.calc_potential(r + step, theta, phi)
+        - internal_field.calc_potential(r - step, theta, phi)
+    ) / (2 * step)
+    assert np.isclose(b_r, internal_field.calc_field(r, theta, phi)[0], rtol=1e-4)
+
+    positions = np.array([[r, theta, phi], [3.0, 0.4, 1.0]])
+    potential = internal_field.parmap_calc_potential(positions)
+    assert potential.shape == (2,)
+    assert np.allclose(potential, internal_field.map_calc_potential(positions))
+
+
+def test_internal_field_degree_range():
+    internal_field = iupitermag.InternalField("JRM09")
+
+    spectrum = internal_field.power_spectrum(1.0)
+    assert spectrum.shape == (11,)
+    assert np.allclose(
+        internal_field.power_spectrum(2.0), spectrum * 0.5 ** (2 * np.arange(11) + 4)
+    )
+
+    b_expected = internal_field.calc_field(1.5, 0.8, 2.5)
+    b_parts = internal_field.degree_range(1, 1).calc_field(1.5, 0.8, 2.5)
+    b_parts += internal_field.degree_range(2, 10).calc_field(1.5, 0.8, 2.5)
+    assert np.allclose(b_parts, b_expected)
+    assert len(internal_field.split_degrees()) == 11
+
+    with pytest.raises(ValueError):
+        internal_field.degree_range(2, 11)
+
+
+def test_internal_field_dipole():
+    dipole = iupitermag.InternalField("JRM09").dipole()
+
+    assert np.isclose(np.degrees(dipole["tilt"]), 10.31, atol=0.01)
+    assert np.isclose(np.degrees(dipole["pole_longitude"]), 196.61, atol=0.01)
+    assert len(dipole["offset"]) == 3
+
+    params = iupitermag.CurrentSheetField("CON2020").get_params()
+    params["theta_d"] = dipole["tilt"]
+    params["phi_d"] = dipole["pole_longitude"]
+    iupitermag.CurrentSheetField("Custom", params)
+
+
+def test_internal_field_rotate():
+    internal_field = iupitermag.InternalField("JRM09")
+
+    angle = 0.7
+    rotation = np.array(
+        [
+            [np.cos(angle), -np.sin(angle), 0.0],
+            [np.sin(angle), np.cos(angle), 0.0],
+            [0.0, 0.0, 1.0],
+        ]
+    )
+    rotated = internal_field.rotate(rotation)
+
+    x = np.array([0.4, -1.1, 0.9])
+    b_expected = rotation @ internal_field.calc_field_xyz(*x)
+    assert np.allclose(rotated.calc_field_xyz(*(rotation @ x)), b_expected)
+
+    g, h = internal_field.in_dipole_frame().get_coefficients()
+    assert np.allclose([g[1, 1], h[1, 1]], 0.0, atol=1e-6)
+
+    with pytest.raises(ValueError):
+        internal_field.rotate(np.ones((3, 3)))
+
+
+def test_external_field(tmp_path):
+    q = np.zeros((2, 2))
+    q[1, 0] = 10.0
+    external_field = iupitermag.ExternalHarmonicField(q, np.zeros((2, 2)))
+
+    # q10 alone is a uniform field along -z.
+    assert np.allclose(external_field.calc_field_xyz(1.0, 2.0, 3.0), [0.0, 0.0, -10.0])
+
+    path = tmp_path / "model.txt"
+    path.write_text("g 1 0 410244.7\nq 1 0 10.0\n")
+    loaded = iupitermag.ExternalHarmonicField.from_file(path)
+    assert np.allclose(loaded.get_coefficients()[0], q)
+    assert iupitermag.InternalField.from_file(path).get_coefficients()[0].shape == (2, 2)
+
+
+def test_internal_field_pole():
+    internal_field = iupitermag.InternalField("JRM33", degree=13)
+
+    # On the pole the field is the limit of the field next to it.
+    b_pole = internal_field.calc_field_xyz(0.0, 0.0, 1.5)
+    b_near = internal_field.calc_field_xyz(1.5e-9, 0.0, 1.5)
+    assert np.allclose(b_pole, b_near, rtol=1e-6)
+
+    b_rtp = internal_field.calc_field(1.5, 0.0, 0.0)
+    assert np.isclose(b_rtp[2], b_pole[1])
+
+
+def test_internal_field_uncertainty():
+    internal_field = iupitermag.InternalField("JRM09")
+
+    with pytest.raises(ValueError):
+        internal_field.calc_field_uncertainty(2.0, 0.7, 1.3)
+
+    # An uncertainty on g10 alone gives the field of a unit axial dipole.
+    sigma_g = np.zeros((2, 2))
+    sigma_g[1, 0] = 1.0
+    field = internal_field.with_standard_deviations(sigma_g, np.zeros((2, 2)))
+    assert field.covariance().shape == (120, 120)
+
+    theta = 0.7
+    expected = [2.0 * np.cos(theta) / 8.0, np.sin(theta) / 8.0, 0.0]
+    assert np.allclose(field.calc_field_uncertainty(2.0, theta, 1.3), expected)
+
+    positions = np.array([[2.0, theta, 1.3], [2.0, theta, 0.1]])
+    assert np.allclose(field.map_calc_field_uncertainty(positions), [expected, expected])
+    assert field.design_matrix_rows(2.0, theta, 1.3).shape == (3, 120)
+
+
+def test_invert_internal_field():
+    internal_field = iupitermag.InternalField("JRM09", degree=3)
+    currentsheet_field = iupitermag.CurrentSheetField("CON2020")
+
+    i = np.arange(300)
+    positions = np.column_stack(
+        [2.0 + (i % 9), 0.05 + 3.0 * i / 300, 0.77 * i]
+    )
+    observed = internal_field.map_calc_field(positions) + currentsheet_field.map_calc_field(
+        positions
+    )
+
+    result = iupitermag.invert_internal_field(
+        positions, observed, 3, currentsheet_field=currentsheet_field
+    )
+    assert result["rms"] < 1e-6
+    assert np.allclose(
+        result["field"].get_coefficients()[0],
+        internal_field.get_coefficients()[0],
+        atol=1e-4,
+    )
+    assert result["field"].covariance().shape == (15, 15)
+
+    matrix = iupitermag.design_matrix(3, positions)
+    assert matrix.shape == (900, 15)
+
+    with pytest.raises(ValueError):
+        iupitermag.invert_internal_field(positions, observed, 3, regularization="unknown")
+
+
+def test_fit_currentsheet():
+    internal_field = iupitermag.InternalField("JRM09", degree=3)
+    currentsheet_field = iupitermag.CurrentSheetField("CON2020")
+
+    i = np.arange(200)
+    positions = np.column_stack([8.0 + (i % 20), 1.2 + 0.0035 * i, 0.61 * i])
+    observed = internal_field.map_calc_field(positions) + currentsheet_field.map_calc_field(
+        positions
+    )
+
+    params = currentsheet_field.get_params()
+    initial = iupitermag.CurrentSheetField(
+        "Custom", {**params, "d": 3.0, "mu0_i_2": 120.0}
+    )
+
+    result = iupitermag.fit_currentsheet(
+        positions, observed, internal_field, initial, frozen=["r_0", "r_1"]
+    )
+    assert result["converged"]
+    assert result["rms"] < 1e-6
+    assert np.isclose(result["params"]["d"], params["d"], rtol=1e-5)
+    assert np.isclose(result["params"]["mu0_i_2"], params["mu0_i_2"], rtol=1e-5)
+    assert len(result["param_names"]) == 5
+    assert result["covariance"].shape == (5, 5)
+
+    with pytest.raises(ValueError):
+        iupitermag.fit_currentsheet(positions, observed, internal_field, initial, frozen=["r_2"])
+
+
+def test_composite_field():
+    internal_field = iupitermag.InternalField("JRM33", degree=10)
+    currentsheet_field = iupitermag.CurrentSheetField("CON2020")
+
+    positions = np.array([[10.0, 1.2, 0.3], [20.0, 1.6, 4.0]])
+    b_expected = internal_field.map_calc_field(positions) + 0.5 * currentsheet_field.map_calc_field(
+        positions
+    )
+
+    field = internal_field + 0.5 * currentsheet_field
+    assert isinstance(field, iupitermag.CompositeField)
+    assert np.allclose(field.map_calc_field(positions), b_expected)
+    assert np.allclose(field.parmap_calc_field(positions), b_expected)
+
+    # Composite fields are flattened, and sum() starts from 0.
+    field = sum([internal_field, currentsheet_field]) + currentsheet_field * -1.0
+    assert np.allclose(
+        field.calc_field(10.0, 1.2, 0.3), internal_field.calc_field(10.0, 1.2, 0.3)
+    )
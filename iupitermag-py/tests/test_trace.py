@@ -1,4 +1,5 @@
 import numpy as np
+import pytest
 
 import iupitermag
 
@@ -16,4 +17,94 @@ def test_trace():
 
     # The point traced to the planet in the northen hemisphere.
     first_expected = np.array([-0.52819934, -0.01459706, 0.77033209])
-    assert np.allclose(first_expected, traces[0][0, :], rtol=1e-3)
+    assert np.allclose(first_expected, traces[0]["positions"][0, :], rtol=1e-3)
+
+    assert traces[0]["topology"] == "closed"
+    assert traces[0]["north"]["termination"] == "planet"
+    assert traces[0]["south"]["termination"] == "planet"
+    assert np.allclose(
+        np.linalg.norm(traces[0]["south"]["b"], axis=1), traces[0]["south"]["b_magnitude"]
+    )
+    assert np.all(np.diff(traces[0]["north"]["arc_length"]) > 0.0)
+
+
+def test_trace_config():
+
+    start_pos = np.array([[-10.0, 2.0, 3.0]])
+
+    internal_field = iupitermag.InternalField("JRM33", degree=10)
+    currentsheet_field = iupitermag.CurrentSheetField("CON2020")
+
+    config = iupitermag.TraceConfig(max_steps=5)
+    traces = iupitermag.trace_field_to_planet(
+        start_pos, internal_field, currentsheet_field, config
+    )
+    assert traces[0]["positions"].shape == (11, 3)
+    assert traces[0]["north"]["termination"] == "max_steps"
+    assert traces[0]["topology"] == "unknown"
+
+    config = iupitermag.TraceConfig(outer_boundary=15.0)
+    traces = iupitermag.trace_field_to_planet(
+        start_pos, internal_field, currentsheet_field, config
+    )
+    assert np.linalg.norm(traces[0]["positions"][-1]) > 15.0
+    assert traces[0]["topology"] == "open"
+
+    with pytest.raises(ValueError):
+        iupitermag.TraceConfig(method="euler")
+
+
+def test_trace_internal_only():
+
+    g = np.array([[0.0, 0.0], [410993.4, 0.0]])
+    internal_field = iupitermag.InternalField("Custom", g=g, h=np.zeros((2, 2)))
+    config = iupitermag.TraceConfig(flattening=0.0)
+
+    traces = iupitermag.trace_field_to_planet(
+        np.array([[5.0, 0.0, 0.0]]), internal_field, None, config
+    )
+
+    # Dipole field lines follow r = L sin^2(theta).
+    positions = traces[0]["positions"]
+    r = np.linalg.norm(positions, axis=1)
+    sin2_theta = (positions[:, 0] ** 2 + positions[:, 1] ** 2) / r**2
+    assert np.allclose(r / sin2_theta, 5.0, atol=1e-3)
+    assert traces[0]["topology"] == "closed"
+
+
+def test_trace_composite_field():
+
+    start_pos = np.array([[-10.0, 2.0, 3.0]])
+
+    internal_field = iupitermag.InternalField("JRM33", degree=10)
+    currentsheet_field = iupitermag.CurrentSheetField("CON2020")
+
+    traces = iupitermag.trace_field_to_planet(
+        start_pos, internal_field + currentsheet_field, None
+    )
+
+    first_expected = np.array([-0.52819934, -0.01459706, 0.77033209])
+    assert np.allclose(first_expected, traces[0]["positions"][0, :], rtol=1e-3)
+
+    # A combined field is traced as it is, without the default current sheet on top.
+    traces_default = iupitermag.trace_field_to_planet(
+        start_pos, internal_field + currentsheet_field
+    )
+    assert np.array_equal(traces_default[0]["positions"], traces[0]["positions"])
+
+
+def test_trace_parallel():
+
+    start_pos = np.array([[-10.0, 2.0, 3.0], [15.0, 0.0, 0.0], [-8.0, -4.0, -1.0]])
+
+    internal_field = iupitermag.InternalField("JRM33", degree=10)
+    currentsheet_field = iupitermag.CurrentSheetField("CON2020")
+
+    traces = iupitermag.trace_field_to_planet(start_pos, internal_field, currentsheet_field)
+    traces_serial = iupitermag.trace_field_to_planet(
+        start_pos, internal_field, currentsheet_field, parallel=False
+    )
+
+    assert len(traces) == 3
+    for trace, trace_serial in zip(traces, traces_serial):
+        assert np.array_equal(trace["positions"], trace_serial["positions"])